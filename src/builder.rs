@@ -0,0 +1,237 @@
+//! Fluent construction of a [`FsTree`].
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use crate::{FsTree, TrieMap};
+
+/// Builder for a [`FsTree`], see [`FsTree::builder`].
+///
+/// Each method accepts multi-component paths, creating missing parent directories (like
+/// `mkdir -p`).
+///
+/// On conflicts, later calls take precedence: a file or symlink replaces whatever was at its path,
+/// and a parent that isn't a directory is replaced by one. `.dir()` on an existing directory keeps
+/// its contents.
+///
+/// # Examples:
+///
+/// ```
+/// use fs_tree::{tree, FsTree};
+///
+/// let result = FsTree::builder()
+///     .file("a/b.txt")
+///     .dir("c")
+///     .symlink("d", "target")
+///     .build();
+///
+/// let expected = tree! {
+///     a: {
+///         "b.txt"
+///     }
+///     c: {}
+///     d -> target
+/// };
+///
+/// assert_eq!(result, expected);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FsTreeBuilder {
+    root: TrieMap,
+}
+
+impl FsTreeBuilder {
+    /// Creates a builder for an empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a regular file at `path`.
+    pub fn file(self, path: impl AsRef<Path>) -> Self {
+        self.node(path.as_ref(), FsTree::Regular)
+    }
+
+    /// Adds a directory at `path`, keeping its contents if it already exists.
+    pub fn dir(mut self, path: impl AsRef<Path>) -> Self {
+        Self::parent_dir(&mut self.root, path.as_ref().iter());
+        self
+    }
+
+    /// Adds a symlink at `path`, pointing to `target`.
+    pub fn symlink(self, path: impl AsRef<Path>, target: impl Into<PathBuf>) -> Self {
+        self.node(path.as_ref(), FsTree::Symlink(target.into()))
+    }
+
+    /// Finishes building, returning the root directory.
+    pub fn build(self) -> FsTree {
+        FsTree::Directory(self.root)
+    }
+
+    fn node(mut self, path: &Path, node: FsTree) -> Self {
+        let mut components = path.iter();
+
+        if let Some(name) = components.next_back() {
+            let parent = Self::parent_dir(&mut self.root, components);
+            parent.insert(name.into(), node);
+        }
+
+        self
+    }
+
+    /// Gets the children of the directory at `components`, creating it and its parents if needed.
+    fn parent_dir<'a>(
+        mut children: &mut TrieMap,
+        components: impl Iterator<Item = &'a OsStr>,
+    ) -> &mut TrieMap {
+        for component in components {
+            let node = children
+                .entry(PathBuf::from(component))
+                .or_insert_with(FsTree::new_dir);
+
+            if !node.is_dir() {
+                *node = FsTree::new_dir();
+            }
+
+            children = node
+                .children_mut()
+                .expect("just made sure it's a directory");
+        }
+
+        children
+    }
+}
+
+impl From<FsTree> for FsTreeBuilder {
+    /// Starts building from an existing tree, if it isn't a directory, starts from an empty one.
+    fn from(tree: FsTree) -> Self {
+        match tree {
+            FsTree::Directory(root) => Self { root },
+            _ => Self::new(),
+        }
+    }
+}
+
+impl FsTree {
+    /// Creates a [`FsTreeBuilder`], for fluent construction of a tree.
+    ///
+    /// Unlike [`FsTree::insert`], missing parent directories are created, so this doesn't panic.
+    pub fn builder() -> FsTreeBuilder {
+        FsTreeBuilder::new()
+    }
+
+    /// Creates a [`FsTreeBuilder`] starting from a copy of `self`, to keep adding nodes to it.
+    ///
+    /// If `self` isn't a directory, the builder starts from an empty one.
+    pub fn to_builder(&self) -> FsTreeBuilder {
+        FsTreeBuilder::from(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tree;
+
+    #[test]
+    fn test_builder_creates_parents() {
+        let result = FsTree::builder()
+            .file("a/b/c")
+            .dir("a/d/e")
+            .symlink("f/link", "target")
+            .build();
+
+        let expected = tree! {
+            a: {
+                b: {
+                    c
+                }
+                d: {
+                    e: {}
+                }
+            }
+            f: {
+                link -> target
+            }
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_builder_insert_complete() {
+        // Same tree as `test_insert_complete`, without inserting the parents first
+        let result = FsTree::builder()
+            .file("config1")
+            .file("config2")
+            .file("outer_dir/file1")
+            .file("outer_dir/file2")
+            .file("outer_dir/inner_dir/inner1")
+            .file("outer_dir/inner_dir/inner2")
+            .file("outer_dir/inner_dir/inner3")
+            .symlink("outer_dir/inner_dir/inner_link", "inner_target")
+            .symlink("link", "target")
+            .file("config3")
+            .build();
+
+        let expected = tree! {
+            config1
+            config2
+            outer_dir: {
+                file1
+                file2
+                inner_dir: {
+                    inner1
+                    inner2
+                    inner3
+                    inner_link -> inner_target
+                }
+            }
+            link -> target
+            config3
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_builder_conflicts() {
+        let result = FsTree::builder()
+            .file("a/b")
+            .dir("a") // Keeps `a/b`
+            .file("c")
+            .file("c/d") // Replaces file `c` by a directory
+            .dir("e")
+            .symlink("e", "target") // Replaces directory `e`
+            .build();
+
+        let expected = tree! {
+            a: {
+                b
+            }
+            c: {
+                d
+            }
+            e -> target
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_to_builder() {
+        let tree = tree! {
+            a: {
+                b
+            }
+        };
+
+        let result = tree.to_builder().file("a/c").build();
+
+        assert_eq!(result, tree! { a: { b c } });
+        assert_eq!(FsTree::Regular.to_builder().build(), FsTree::new_dir());
+    }
+}
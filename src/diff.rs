@@ -0,0 +1,431 @@
+//! Comparison between two [`FsTree`]s.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use file_type_enum::FileType;
+
+use crate::FsTree;
+
+/// A list of differences between two trees, see [`FsTree::diff`] and
+/// [`FsTree::diff_with_moves`].
+pub type DiffTree = Vec<FsTreeDiff>;
+
+/// A difference found when comparing a left tree (`self`) against a right tree (`other`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsTreeDiff {
+    /// Node only present in the left tree.
+    ///
+    /// [`FsTree::diff`] lists a removed directory once, without its children, while
+    /// [`FsTree::diff_with_moves`] lists each of its leaves instead.
+    OnlyInLeft(PathBuf),
+    /// Node only present in the right tree.
+    ///
+    /// [`FsTree::diff`] lists an added directory once, without its children, while
+    /// [`FsTree::diff_with_moves`] lists each of its leaves instead.
+    OnlyInRight(PathBuf),
+    /// Node present in both trees, but with different file types.
+    TypeChanged {
+        /// Path of both nodes.
+        path: PathBuf,
+        /// File type in the left tree.
+        left: FileType,
+        /// File type in the right tree.
+        right: FileType,
+    },
+    /// Symlink present in both trees, but with different targets.
+    SymlinkTargetChanged {
+        /// Path of both symlinks.
+        path: PathBuf,
+        /// Target in the left tree.
+        left: PathBuf,
+        /// Target in the right tree.
+        right: PathBuf,
+    },
+    /// Node only present at `from` in the left tree, and at `to` in the right tree.
+    Moved {
+        /// Path in the left tree.
+        from: PathBuf,
+        /// Path in the right tree.
+        to: PathBuf,
+    },
+}
+
+impl FsTree {
+    /// Compare two trees, detecting leaves that were moved.
+    ///
+    /// Removed and added directories are listed by their leaves, so a moved file shows up as a
+    /// [`FsTreeDiff::OnlyInLeft`] and a [`FsTreeDiff::OnlyInRight`], these are paired into a
+    /// single [`FsTreeDiff::Moved`] when:
+    ///
+    /// 1. Both are leaves (regular files, symlinks or empty directories) and are equal (same type,
+    ///    and same target, for symlinks).
+    /// 2. Both have the same file name.
+    /// 3. The pairing is unambiguous, that is, no other removed or added leaf matches the same
+    ///    name and node.
+    ///
+    /// File contents aren't tracked, so files with the same name might be falsely paired.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTreeDiff};
+    ///
+    /// let left = tree! {
+    ///     old_dir: {
+    ///         file
+    ///     }
+    /// };
+    /// let right = tree! {
+    ///     new_dir: {
+    ///         file
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(left.diff_with_moves(&right), [FsTreeDiff::Moved {
+    ///     from: "old_dir/file".into(),
+    ///     to: "new_dir/file".into(),
+    /// }]);
+    /// ```
+    pub fn diff_with_moves(&self, other: &Self) -> DiffTree {
+        // Moves are detected across leaves, so removed and added directories are expanded
+        let mut diffs = Vec::new();
+
+        for diff in self.diff(other) {
+            match diff {
+                FsTreeDiff::OnlyInLeft(path) => {
+                    diffs.extend(leaves_at(self, &path).map(FsTreeDiff::OnlyInLeft));
+                },
+                FsTreeDiff::OnlyInRight(path) => {
+                    diffs.extend(leaves_at(other, &path).map(FsTreeDiff::OnlyInRight));
+                },
+                diff => diffs.push(diff),
+            }
+        }
+
+        let removed: Vec<&PathBuf> = diffs.iter().filter_map(only_in_left).collect();
+        let added: Vec<&PathBuf> = diffs.iter().filter_map(only_in_right).collect();
+
+        let mut moves = Vec::new();
+
+        for from in &removed {
+            let key = (from.file_name(), self.get(from));
+
+            let mut same_removed = removed
+                .iter()
+                .filter(|path| (path.file_name(), self.get(path)) == key);
+            let mut same_added = added
+                .iter()
+                .filter(|path| (path.file_name(), other.get(path)) == key);
+
+            if let (Some(_), None, Some(to), None) = (
+                same_removed.next(),
+                same_removed.next(),
+                same_added.next(),
+                same_added.next(),
+            ) {
+                moves.push((PathBuf::clone(from), PathBuf::clone(to)));
+            }
+        }
+
+        for (from, to) in moves {
+            diffs.retain(|diff| only_in_right(diff) != Some(&to));
+
+            let removal = diffs
+                .iter_mut()
+                .find(|diff| only_in_left(diff) == Some(&from))
+                .expect("removal was collected from this list");
+
+            *removal = FsTreeDiff::Moved { from, to };
+        }
+
+        diffs
+    }
+
+    /// Compare two trees, listing every difference between them.
+    ///
+    /// Unlike `==`, which only tells if they're equal, this is useful for diagnostics, like
+    /// reporting all mismatches when comparing large trees in tests.
+    ///
+    /// A directory only present in one of the trees is listed once, without its children.
+    ///
+    /// Differences are sorted by path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTreeDiff};
+    /// use file_type_enum::FileType;
+    ///
+    /// let left = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    ///     changed
+    ///     link -> a
+    /// };
+    /// let right = tree! {
+    ///     changed: {}
+    ///     link -> b
+    ///     new
+    /// };
+    ///
+    /// assert_eq!(left.diff(&right), [
+    ///     FsTreeDiff::TypeChanged {
+    ///         path: "changed".into(),
+    ///         left: FileType::Regular,
+    ///         right: FileType::Directory,
+    ///     },
+    ///     FsTreeDiff::OnlyInLeft("dir".into()),
+    ///     FsTreeDiff::SymlinkTargetChanged {
+    ///         path: "link".into(),
+    ///         left: "a".into(),
+    ///         right: "b".into(),
+    ///     },
+    ///     FsTreeDiff::OnlyInRight("new".into()),
+    /// ]);
+    /// ```
+    pub fn diff(&self, other: &Self) -> DiffTree {
+        let mut diffs = Vec::new();
+        self.__diff_at(other, &mut PathBuf::new(), &mut diffs);
+        diffs
+    }
+
+    fn __diff_at(&self, other: &Self, path: &mut PathBuf, diffs: &mut DiffTree) {
+        match (self, other) {
+            (Self::Directory(left_children), Self::Directory(right_children)) => {
+                let names: BTreeSet<&PathBuf> =
+                    left_children.keys().chain(right_children.keys()).collect();
+
+                for name in names {
+                    path.push(name);
+
+                    match (left_children.get(name), right_children.get(name)) {
+                        (Some(left), Some(right)) => left.__diff_at(right, path, diffs),
+                        (Some(_), None) => diffs.push(FsTreeDiff::OnlyInLeft(path.clone())),
+                        (None, Some(_)) => diffs.push(FsTreeDiff::OnlyInRight(path.clone())),
+                        (None, None) => unreachable!("name comes from one of the maps"),
+                    }
+
+                    path.pop();
+                }
+            },
+            (Self::Regular, Self::Regular) => {},
+            (Self::Symlink(left), Self::Symlink(right)) => {
+                if left != right {
+                    diffs.push(FsTreeDiff::SymlinkTargetChanged {
+                        path: path.clone(),
+                        left: left.clone(),
+                        right: right.clone(),
+                    });
+                }
+            },
+            (left, right) => {
+                diffs.push(FsTreeDiff::TypeChanged {
+                    path: path.clone(),
+                    left: left.file_type(),
+                    right: right.file_type(),
+                });
+            },
+        }
+    }
+}
+
+fn only_in_left(diff: &FsTreeDiff) -> Option<&PathBuf> {
+    match diff {
+        FsTreeDiff::OnlyInLeft(path) => Some(path),
+        _ => None,
+    }
+}
+
+fn only_in_right(diff: &FsTreeDiff) -> Option<&PathBuf> {
+    match diff {
+        FsTreeDiff::OnlyInRight(path) => Some(path),
+        _ => None,
+    }
+}
+
+/// Full paths of the leaves under `path`, or `path` itself if it's a leaf.
+fn leaves_at<'a>(tree: &'a FsTree, path: &'a Path) -> impl Iterator<Item = PathBuf> + 'a {
+    let node = tree
+        .get(path)
+        .expect("path comes from a diff against this tree");
+
+    node.iter()
+        .filter(|(node, _)| node.is_leaf())
+        .map(move |(_, relative)| path.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tree;
+
+    #[test]
+    fn test_diff_lists_every_difference() {
+        let left = tree! {
+            a: {
+                b: {
+                    c
+                    d
+                }
+            }
+            same: {
+                file
+            }
+        };
+        let right = tree! {
+            a: {
+                b: {
+                    c -> target
+                    e
+                }
+            }
+            same: {
+                file
+            }
+        };
+
+        let expected = [
+            FsTreeDiff::TypeChanged {
+                path: "a/b/c".into(),
+                left: FileType::Regular,
+                right: FileType::Symlink,
+            },
+            FsTreeDiff::OnlyInLeft("a/b/d".into()),
+            FsTreeDiff::OnlyInRight("a/b/e".into()),
+        ];
+
+        assert_eq!(left.diff(&right), expected);
+        assert!(left.diff(&left).is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let left = tree! {
+            removed
+            removed_dir: {
+                file
+            }
+            becomes_dir
+            dir: {
+                kept
+                link -> a
+            }
+        };
+        let right = tree! {
+            becomes_dir: {
+                file
+            }
+            dir: {
+                kept
+                added
+                link -> b
+            }
+        };
+
+        let expected = [
+            FsTreeDiff::TypeChanged {
+                path: "becomes_dir".into(),
+                left: FileType::Regular,
+                right: FileType::Directory,
+            },
+            FsTreeDiff::OnlyInRight("dir/added".into()),
+            FsTreeDiff::SymlinkTargetChanged {
+                path: "dir/link".into(),
+                left: "a".into(),
+                right: "b".into(),
+            },
+            FsTreeDiff::OnlyInLeft("removed".into()),
+            FsTreeDiff::OnlyInLeft("removed_dir".into()),
+        ];
+
+        assert_eq!(left.diff(&right), expected);
+
+        // Swapping sides swaps the diffs
+        let swapped: Vec<_> = right
+            .diff(&left)
+            .into_iter()
+            .filter(|diff| matches!(diff, FsTreeDiff::OnlyInLeft(_)))
+            .collect();
+        assert_eq!(swapped, [FsTreeDiff::OnlyInLeft("dir/added".into())]);
+    }
+
+    #[test]
+    fn test_diff_with_moves() {
+        let left = tree! {
+            moved_file
+            moved_link -> target
+            dir: {
+                retargeted_link -> target
+                removed
+            }
+            changed_type
+        };
+        let right = tree! {
+            dir: {
+                moved_file
+                retargeted_link -> other_target
+                added: {}
+            }
+            other_dir: {
+                moved_link -> target
+            }
+            changed_type: {}
+        };
+
+        let expected = vec![
+            FsTreeDiff::TypeChanged {
+                path: "changed_type".into(),
+                left: FileType::Regular,
+                right: FileType::Directory,
+            },
+            FsTreeDiff::OnlyInRight("dir/added".into()),
+            FsTreeDiff::OnlyInLeft("dir/removed".into()),
+            FsTreeDiff::SymlinkTargetChanged {
+                path: "dir/retargeted_link".into(),
+                left: "target".into(),
+                right: "other_target".into(),
+            },
+            FsTreeDiff::Moved {
+                from: "moved_file".into(),
+                to: "dir/moved_file".into(),
+            },
+            FsTreeDiff::Moved {
+                from: "moved_link".into(),
+                to: "other_dir/moved_link".into(),
+            },
+        ];
+
+        assert_eq!(left.diff_with_moves(&right), expected);
+    }
+
+    #[test]
+    fn test_diff_with_moves_skips_ambiguous_pairs() {
+        let left = tree! {
+            a: {
+                file
+            }
+            b: {
+                file
+            }
+        };
+        let right = tree! {
+            c: {
+                file
+            }
+        };
+
+        let expected = vec![
+            FsTreeDiff::OnlyInLeft("a/file".into()),
+            FsTreeDiff::OnlyInLeft("b/file".into()),
+            FsTreeDiff::OnlyInRight("c/file".into()),
+        ];
+
+        assert_eq!(left.diff_with_moves(&right), expected);
+    }
+}
@@ -0,0 +1,205 @@
+//! Reading trees from a custom source of directory entries, see [`DirSource`].
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    utils::{self, fs},
+    Error, FsTree, NodeKind, Result, TrieMap,
+};
+
+/// A source of directory entries, used by [`FsTree::read_with`] to build a tree.
+///
+/// This decouples reading from the real filesystem, so you can build trees from virtual
+/// filesystems, or test tree-building logic with a fake source.
+///
+/// [`StdDirSource`] is the implementation for the real filesystem.
+pub trait DirSource {
+    /// Lists the entries of the directory at `path`, each one with its file name and kind.
+    ///
+    /// Entries may be returned in any order.
+    ///
+    /// # Errors:
+    ///
+    /// - If the directory can't be listed, an [`Error::IoError`] gets the path attached by
+    ///   [`FsTree::read_with`].
+    /// - [`Error::UnexpectedFileTypeError`] for entries that aren't regular files, directories or
+    ///   symlinks.
+    fn read_dir(&self, path: &Path) -> Result<Vec<(PathBuf, NodeKind)>>;
+}
+
+/// [`DirSource`] that reads from the real filesystem, using `std::fs`.
+///
+/// Symlinks are not followed, they're listed with their targets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdDirSource;
+
+impl DirSource for StdDirSource {
+    fn read_dir(&self, path: &Path) -> Result<Vec<(PathBuf, NodeKind)>> {
+        let mut entries = vec![];
+
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+
+            let kind = if file_type.is_file() {
+                NodeKind::Regular
+            } else if file_type.is_dir() {
+                NodeKind::Directory
+            } else if file_type.is_symlink() {
+                NodeKind::Symlink(fs::read_link(entry.path())?)
+            } else {
+                return Err(Error::UnexpectedFileTypeError(
+                    file_type.into(),
+                    entry.path(),
+                ));
+            };
+
+            entries.push((PathBuf::from(entry.file_name()), kind));
+        }
+
+        Ok(entries)
+    }
+}
+
+impl FsTree {
+    /// Construct a `FsTree` by recursively listing the directory at `path` from `source`.
+    ///
+    /// The root is always a directory, and only entries reported as [`NodeKind::Directory`] are
+    /// listed, so `source` decides whether symlinks are followed.
+    ///
+    /// # Errors:
+    ///
+    /// - If `source` fails to list any directory, IO errors carry the path, with
+    ///   [`Error::NotFoundError`] and [`Error::NotADirectoryError`] for the matching
+    ///   [`io::ErrorKind`]s.
+    ///
+    /// [`io::ErrorKind`]: std::io::ErrorKind
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::{FsTree, StdDirSource};
+    ///
+    /// let tree = FsTree::read_with(&StdDirSource, "src").unwrap();
+    /// ```
+    pub fn read_with(source: &impl DirSource, path: impl AsRef<Path>) -> Result<Self> {
+        let mut path = path.as_ref().to_path_buf();
+        Self::__read_with(source, &mut path)
+    }
+
+    fn __read_with(source: &impl DirSource, path: &mut PathBuf) -> Result<Self> {
+        let mut children = TrieMap::new();
+
+        let entries = source.read_dir(path).map_err(|error| {
+            match error {
+                Error::IoError(error) => utils::io_error_at(error, path),
+                error => error,
+            }
+        })?;
+
+        for (name, kind) in entries {
+            let child = match kind {
+                NodeKind::Directory => {
+                    path.push(&name);
+                    let child = Self::__read_with(source, path);
+                    path.pop();
+                    child?
+                },
+                kind => kind.into(),
+            };
+
+            children.insert(name, child);
+        }
+
+        Ok(Self::Directory(children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io};
+
+    use file_type_enum::FileType;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{tree, utils::testdir};
+
+    struct FakeSource(HashMap<PathBuf, Vec<(PathBuf, NodeKind)>>);
+
+    impl DirSource for FakeSource {
+        fn read_dir(&self, path: &Path) -> Result<Vec<(PathBuf, NodeKind)>> {
+            self.0
+                .get(path)
+                .cloned()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound).into())
+        }
+    }
+
+    #[test]
+    fn test_read_with_fake_source() {
+        let source = FakeSource(HashMap::from([
+            (
+                "/root".into(),
+                vec![
+                    ("file".into(), NodeKind::Regular),
+                    ("dir".into(), NodeKind::Directory),
+                ],
+            ),
+            (
+                "/root/dir".into(),
+                vec![
+                    ("link".into(), NodeKind::Symlink("../file".into())),
+                    ("empty".into(), NodeKind::Directory),
+                ],
+            ),
+            ("/root/dir/empty".into(), vec![]),
+        ]));
+
+        let result = FsTree::read_with(&source, "/root").unwrap();
+        let expected = tree! {
+            file
+            dir: {
+                link -> "../file"
+                empty: {}
+            }
+        };
+
+        assert_eq!(result, expected);
+        assert!(matches!(
+            FsTree::read_with(&source, "/missing"),
+            Err(Error::NotFoundError(path)) if path == Path::new("/missing")
+        ));
+    }
+
+    #[test]
+    fn test_read_with_std_source() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+                link -> file
+            }
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let result = FsTree::read_with(&StdDirSource, test_dir).unwrap();
+        assert_eq!(result, tree);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_with_std_source_unexpected_file_type() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let socket = test_dir.join("socket");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+        let error = FsTree::read_with(&StdDirSource, test_dir).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::UnexpectedFileTypeError(FileType::Socket, path) if path == socket
+        ));
+    }
+}
@@ -0,0 +1,269 @@
+//! Implementation of [`fmt::Display`] for [`FsTree`], and flat path listings.
+
+use std::{
+    fmt::{self, Write},
+    path::Path,
+};
+
+use crate::FsTree;
+
+impl fmt::Display for FsTree {
+    /// Renders the classic `tree` command diagram.
+    ///
+    /// The root is unnamed, so it's rendered as `.`, children are listed in the `TrieMap` order,
+    /// and symlinks are rendered as `name -> target`.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///         link -> target
+    ///     }
+    ///     empty: {}
+    /// };
+    ///
+    /// let expected = "\
+    /// .
+    /// ├── dir
+    /// │   ├── file
+    /// │   └── link -> target
+    /// └── empty
+    /// ";
+    ///
+    /// assert_eq!(tree.to_string(), expected);
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_node(f, Path::new("."), self)?;
+        write_children(f, self, &mut vec![])
+    }
+}
+
+/// Options for [`FsTree::paths_string_with`].
+///
+/// The default options disable everything, [`FsTree::paths_string`] enables the trailing slash.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathsStringOptions {
+    trailing_slash: bool,
+    symlink_targets: bool,
+}
+
+impl PathsStringOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a `/` to directory paths, so they're visually distinct from files.
+    pub fn trailing_slash(mut self, arg: bool) -> Self {
+        self.trailing_slash = arg;
+        self
+    }
+
+    /// Show symlinks as `path -> target`.
+    pub fn symlink_targets(mut self, arg: bool) -> Self {
+        self.symlink_targets = arg;
+        self
+    }
+}
+
+impl FsTree {
+    /// Lists all paths, one per line, with a trailing `/` for directories.
+    ///
+    /// The root has no path, so it's not listed. Lines aren't terminated by a newline, so an
+    /// empty tree gives an empty string.
+    ///
+    /// This is a flatter alternative to the [`fmt::Display`] diagram, handy for snapshot tests.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    ///     link -> dir
+    /// };
+    ///
+    /// assert_eq!(tree.paths_string(), "dir/\ndir/file\nlink");
+    /// ```
+    pub fn paths_string(&self) -> String {
+        self.paths_string_with(PathsStringOptions::new().trailing_slash(true))
+    }
+
+    /// Lists all paths, one per line, formatted according to `options`.
+    ///
+    /// See [`FsTree::paths_string`] and [`PathsStringOptions`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, PathsStringOptions};
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    ///     link -> dir
+    /// };
+    ///
+    /// let options = PathsStringOptions::new().symlink_targets(true);
+    ///
+    /// assert_eq!(tree.paths_string_with(options), "dir\ndir/file\nlink -> dir");
+    /// ```
+    pub fn paths_string_with(&self, options: PathsStringOptions) -> String {
+        let mut string = String::new();
+
+        for (node, path) in self.iter().skip(1) {
+            if !string.is_empty() {
+                string.push('\n');
+            }
+
+            // Writing to a `String` never fails
+            let _ = write!(string, "{}", path.display());
+
+            match node {
+                Self::Directory(_) if options.trailing_slash => string.push('/'),
+                Self::Symlink(target) if options.symlink_targets => {
+                    let _ = write!(string, " -> {}", target.display());
+                },
+                _ => {},
+            }
+        }
+
+        string
+    }
+}
+
+/// Writes a single line for `node`, without the prefix.
+fn write_node(f: &mut fmt::Formatter, name: &Path, node: &FsTree) -> fmt::Result {
+    match node {
+        FsTree::Symlink(target) => writeln!(f, "{} -> {}", name.display(), target.display()),
+        _ => writeln!(f, "{}", name.display()),
+    }
+}
+
+/// Writes the children of `node`, recursively.
+///
+/// `is_last` holds, for each ancestor level, whether that ancestor was the last of its siblings,
+/// to decide between drawing a vertical line or blank space.
+fn write_children(f: &mut fmt::Formatter, node: &FsTree, is_last: &mut Vec<bool>) -> fmt::Result {
+    let Some(children) = node.children() else {
+        return Ok(());
+    };
+
+    for (index, (name, child)) in children.iter().enumerate() {
+        let is_last_child = index + 1 == children.len();
+
+        for &ancestor_is_last in is_last.iter() {
+            f.write_str(if ancestor_is_last { "    " } else { "│   " })?;
+        }
+        f.write_str(if is_last_child {
+            "└── "
+        } else {
+            "├── "
+        })?;
+
+        write_node(f, name, child)?;
+
+        is_last.push(is_last_child);
+        write_children(f, child, is_last)?;
+        is_last.pop();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tree;
+
+    #[test]
+    fn test_display() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                    d: {}
+                }
+                e -> "../target"
+            }
+            f: {
+                g
+            }
+            h
+        };
+
+        let expected = "\
+.
+├── a
+│   ├── b
+│   │   ├── c
+│   │   └── d
+│   └── e -> ../target
+├── f
+│   └── g
+└── h
+";
+
+        assert_eq!(tree.to_string(), expected);
+    }
+
+    #[test]
+    fn test_display_root_only() {
+        assert_eq!(FsTree::new_dir().to_string(), ".\n");
+        assert_eq!(FsTree::Regular.to_string(), ".\n");
+        assert_eq!(
+            FsTree::Symlink("target".into()).to_string(),
+            ". -> target\n"
+        );
+    }
+
+    #[test]
+    fn test_paths_string() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                }
+                link -> "../target"
+            }
+            empty: {}
+            file
+        };
+
+        let expected = "\
+a/
+a/b/
+a/b/c
+a/link
+empty/
+file";
+        assert_eq!(tree.paths_string(), expected);
+
+        let options = PathsStringOptions::new()
+            .trailing_slash(true)
+            .symlink_targets(true);
+        let expected = "\
+a/
+a/b/
+a/b/c
+a/link -> ../target
+empty/
+file";
+        assert_eq!(tree.paths_string_with(options), expected);
+
+        let expected = "a\na/b\na/b/c\na/link\nempty\nfile";
+        assert_eq!(tree.paths_string_with(PathsStringOptions::new()), expected);
+
+        assert_eq!(FsTree::new_dir().paths_string(), "");
+    }
+}
@@ -0,0 +1,198 @@
+//! Entry API for the nodes of a [`FsTree`], see [`FsTree::entry`].
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::FsTree;
+
+/// A view into the node at a path of a [`FsTree`], which may or may not exist.
+///
+/// Created by [`FsTree::entry`], similar to [`BTreeMap::entry`].
+///
+/// The tree isn't changed until a node is inserted, missing parent directories are only created
+/// by [`Entry::or_insert`] and [`Entry::or_insert_with`].
+///
+/// [`BTreeMap::entry`]: std::collections::BTreeMap::entry
+#[derive(Debug)]
+pub struct Entry<'a> {
+    tree: &'a mut FsTree,
+    path: PathBuf,
+}
+
+impl<'a> Entry<'a> {
+    /// The path of the entry, relative to the root, without `.` components.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns `true` if there's a node at the path.
+    pub fn is_occupied(&self) -> bool {
+        self.tree.contains(&self.path)
+    }
+
+    /// Returns the node at the path, inserting `default` if there's none.
+    ///
+    /// # Panics:
+    ///
+    /// - If inserting, and a parent exists, but isn't a directory.
+    pub fn or_insert(self, default: FsTree) -> &'a mut FsTree {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns the node at the path, inserting the result of `default` if there's none.
+    ///
+    /// Missing parent directories are created, like with [`FsTree::get_or_insert_dir`].
+    ///
+    /// # Panics:
+    ///
+    /// - If inserting, and a parent exists, but isn't a directory.
+    pub fn or_insert_with(self, default: impl FnOnce() -> FsTree) -> &'a mut FsTree {
+        if self.is_occupied() {
+            return self.tree.get_mut(&self.path).expect("node exists");
+        }
+
+        // Not occupied, so it's not the root, and `FsTree::entry` only keeps normal components
+        let name = self
+            .path
+            .file_name()
+            .expect("path ends with a normal component");
+        let parent = self.path.parent().unwrap_or(Path::new(""));
+
+        let children = self
+            .tree
+            .get_or_insert_dir(parent)
+            .children_mut()
+            .expect("get_or_insert_dir returns a directory");
+
+        children.entry(PathBuf::from(name)).or_insert_with(default)
+    }
+
+    /// Calls `f` with the node at the path, if any, and returns the entry back.
+    pub fn and_modify(self, f: impl FnOnce(&mut FsTree)) -> Self {
+        if let Some(node) = self.tree.get_mut(&self.path) {
+            f(node);
+        }
+        self
+    }
+}
+
+impl FsTree {
+    /// Gets the [`Entry`] for the node at the path, for in-place insertion or modification.
+    ///
+    /// `.` components are ignored, and an empty path is the root, which always exists.
+    ///
+    /// When inserting, missing parent directories are created, the tree is left unchanged if
+    /// nothing is inserted.
+    ///
+    /// # Panics:
+    ///
+    /// - If the path has a `..` component, or is absolute, these can't name a node of the tree.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let mut tree = FsTree::new_dir();
+    ///
+    /// tree.entry("a/b/c").or_insert(FsTree::Regular);
+    /// assert_eq!(tree, tree! { a: { b: { c } } });
+    ///
+    /// // Already exists, so it's kept
+    /// tree.entry("a/b/c").or_insert(FsTree::new_dir());
+    /// assert_eq!(tree["a/b/c"], FsTree::Regular);
+    ///
+    /// tree.entry("a/b/link")
+    ///     .and_modify(|node| *node = FsTree::Symlink("c".into()))
+    ///     .or_insert_with(|| FsTree::Symlink("new".into()));
+    /// assert_eq!(tree["a/b/link"], FsTree::Symlink("new".into()));
+    /// ```
+    pub fn entry(&mut self, path: impl AsRef<Path>) -> Entry<'_> {
+        let path = path.as_ref();
+
+        let path = path
+            .components()
+            .filter_map(|component| {
+                match component {
+                    Component::Normal(piece) => Some(piece),
+                    Component::CurDir => None,
+                    Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                        panic!(
+                            "entry path can't have `..` or be absolute: '{}'",
+                            path.display()
+                        )
+                    },
+                }
+            })
+            .collect();
+
+        Entry { tree: self, path }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::{tree, FsTree};
+
+    #[test]
+    fn test_entry() {
+        let mut tree = FsTree::new_dir();
+
+        // Vacant, creating the missing parents
+        let node = tree.entry("a/b/c").or_insert(FsTree::Regular);
+        assert_eq!(node, &mut FsTree::Regular);
+        assert_eq!(tree, tree! { a: { b: { c } } });
+
+        // Occupied, nothing is inserted
+        tree.entry("./a/b/c").or_insert(FsTree::new_dir());
+        tree.entry("a").or_insert_with(|| panic!("not called"));
+        assert_eq!(tree, tree! { a: { b: { c } } });
+
+        // `and_modify` only runs for occupied entries, and doesn't create parents
+        tree.entry("x/y").and_modify(|_| panic!("not called"));
+        assert!(!tree.contains("x"));
+
+        tree.entry("a/b/c")
+            .and_modify(|node| *node = FsTree::Symlink("target".into()))
+            .or_insert(FsTree::Regular);
+        assert_eq!(tree["a/b/c"], FsTree::Symlink("target".into()));
+
+        // The root always exists
+        let entry = tree.entry(".");
+        assert!(entry.is_occupied());
+        assert_eq!(entry.path(), "");
+        assert!(tree.entry("").or_insert(FsTree::Regular).is_dir());
+
+        // Composes with `get_or_insert_dir`
+        tree.get_or_insert_dir("d")
+            .entry("e")
+            .or_insert(FsTree::Regular);
+        assert_eq!(
+            tree,
+            tree! {
+                a: { b: { c -> target } }
+                d: { e }
+            }
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_entry_through_file() {
+        let mut tree = tree! { file };
+        tree.entry("file/inner").or_insert(FsTree::Regular);
+    }
+
+    #[test]
+    #[should_panic(expected = "entry path can't have `..` or be absolute: 'a/..'")]
+    fn test_entry_parent_dir() {
+        FsTree::new_dir().entry("a/..");
+    }
+
+    #[test]
+    #[should_panic(expected = "entry path can't have `..` or be absolute: '/abs/x'")]
+    fn test_entry_absolute() {
+        FsTree::new_dir().entry("/abs/x");
+    }
+}
@@ -6,16 +6,31 @@ use file_type_enum::FileType;
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// An enum for all errors generated in the `fs-tree` crate.
+///
+/// New variants might be added in minor releases, so matches need a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Expected directory, but file type differs.
     NotADirectoryError(PathBuf),
     /// Expected symlink, but file type differs.
     NotASymlinkError(PathBuf),
+    /// Expected a file, but nothing was found at the path.
+    NotFoundError(PathBuf),
     /// Unsupported file type found.
     UnexpectedFileTypeError(FileType, PathBuf),
+    /// Following the symlink at the path leads back to itself, or to one of its parents.
+    SymlinkLoopError(PathBuf),
+    /// Writing the node at the path would create a file outside of the destination folder,
+    /// because a node name isn't a single normal component, like `..`, `/etc` or `a/b`.
+    EscapingPathError(PathBuf),
     /// An error with reading or writing.
+    ///
+    /// Only built by the `From<io::Error>` conversion, errors from this crate carry the failing
+    /// path in [`Error::PathIoError`] instead.
     IoError(io::Error),
+    /// An error with reading or writing at the given path.
+    PathIoError(io::Error, PathBuf),
 }
 
 use Error::*;
@@ -26,7 +41,11 @@ impl Error {
         match self {
             NotADirectoryError(path)
             | NotASymlinkError(path)
-            | UnexpectedFileTypeError(_, path) => Some(path),
+            | NotFoundError(path)
+            | UnexpectedFileTypeError(_, path)
+            | SymlinkLoopError(path)
+            | EscapingPathError(path)
+            | PathIoError(_, path) => Some(path),
             IoError(..) => None,
         }
     }
@@ -35,7 +54,7 @@ impl Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
-            IoError(source) => Some(source),
+            IoError(source) | PathIoError(source, _) => Some(source),
             _ => None,
         }
     }
@@ -48,8 +67,12 @@ impl fmt::Display for Error {
         match self {
             NotADirectoryError(..) => write!(f, "not a directory"),
             NotASymlinkError(..) => write!(f, "not a symlink"),
+            NotFoundError(..) => write!(f, "not found"),
             UnexpectedFileTypeError(..) => write!(f, "unexpected file type"),
+            SymlinkLoopError(..) => write!(f, "symlink loop"),
+            EscapingPathError(..) => write!(f, "path escapes the destination folder"),
             IoError(inner) => inner.fmt(f),
+            PathIoError(inner, path) => write!(f, "{inner} (at {})", path.display()),
         }
     }
 }
@@ -59,3 +82,124 @@ impl From<io::Error> for Error {
         Error::IoError(err)
     }
 }
+
+/// An error returned by [`FsTree::try_insert`] when a parent of the inserted node can't hold it.
+///
+/// [`FsTree::try_insert`]: crate::FsTree::try_insert
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InsertError {
+    /// A parent directory doesn't exist.
+    MissingParent(PathBuf),
+    /// A parent exists, but isn't a directory.
+    NotADirectory {
+        /// Path of the parent.
+        path: PathBuf,
+        /// The [`FsTree::variant_str`] of the parent.
+        ///
+        /// [`FsTree::variant_str`]: crate::FsTree::variant_str
+        variant: &'static str,
+    },
+}
+
+/// An error returned by [`FsTree::rename`].
+///
+/// [`FsTree::rename`]: crate::FsTree::rename
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    /// There's no node at the source path.
+    NotFound(PathBuf),
+    /// There's already a node at the destination path, and overwriting is disabled.
+    AlreadyExists(PathBuf),
+    /// The destination is inside of the source (or the source is the root), so the node can't
+    /// be moved into itself.
+    IntoDescendant {
+        /// The source path.
+        from: PathBuf,
+        /// The destination path.
+        to: PathBuf,
+    },
+    /// A parent of the destination can't hold the node.
+    Destination(InsertError),
+}
+
+/// An error returned by [`FsTree::from_path_text_checked`] for paths that could escape the
+/// directory the tree is relative to.
+///
+/// Both variants carry the rejected path.
+///
+/// [`FsTree::from_path_text_checked`]: crate::FsTree::from_path_text_checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The path has a `..` component.
+    ParentDir(PathBuf),
+    /// The path is absolute, or has a prefix, like `C:` on Windows.
+    Absolute(PathBuf),
+}
+
+impl error::Error for PathError {}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ParentDir(path) => {
+                write!(f, "path {} has a `..` component", path.display())
+            },
+            Self::Absolute(path) => write!(f, "path {} is not relative", path.display()),
+        }
+    }
+}
+
+impl error::Error for RenameError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Destination(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "no node at {}", path.display()),
+            Self::AlreadyExists(path) => write!(f, "a node already exists at {}", path.display()),
+            Self::IntoDescendant { from, to } => {
+                write!(
+                    f,
+                    "can't move {} into its own descendant {}",
+                    from.display(),
+                    to.display()
+                )
+            },
+            Self::Destination(inner) => inner.fmt(f),
+        }
+    }
+}
+
+impl InsertError {
+    /// The path of the parent that failed.
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Self::MissingParent(path) | Self::NotADirectory { path, .. } => path,
+        }
+    }
+}
+
+impl error::Error for InsertError {}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingParent(path) => {
+                write!(f, "parent directory {} doesn't exist", path.display())
+            },
+            Self::NotADirectory { path, variant } => {
+                write!(
+                    f,
+                    "parent {} is not a directory, but a {variant}",
+                    path.display()
+                )
+            },
+        }
+    }
+}
@@ -1,24 +1,105 @@
 //! Implementation of [`FsTree`].
 
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     ffi::OsStr,
     io, mem,
     ops::Index,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 
 use file_type_enum::FileType;
 
 use crate::{
-    iter::{Iter, NodesIter, PathsIter},
+    iter::{
+        IntoNodesIter, Iter, NamedNodesIter, NodesIter, NodesIterMut, PathsIter, PostOrderPathsIter,
+    },
     utils::{self, fs},
-    Error, Result,
+    Error, InsertError, NodeKind, NodeMetadata, PathError, Result, SideTable,
 };
 
 /// The children [Trie](https://en.wikipedia.org/wiki/Trie) type alias.
 pub type TrieMap = BTreeMap<PathBuf, FsTree>;
 
+/// What to do after visiting a node in [`FsTree::walk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WalkAction {
+    /// Keep walking, descending into the node's children, if any.
+    #[default]
+    Continue,
+    /// Keep walking, but don't descend into the node's children.
+    SkipSubtree,
+    /// Stop walking, no other node is visited.
+    Stop,
+}
+
+/// A filesystem operation performed by [`FsTree::write_at`], see [`FsTree::write_plan`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WriteAction {
+    /// Create a regular file at the path.
+    CreateFile(PathBuf),
+    /// Create a directory at the path.
+    CreateDir(PathBuf),
+    /// Create a symlink at `path`, pointing to `target`.
+    CreateSymlink {
+        /// Where the symlink is created.
+        path: PathBuf,
+        /// What the symlink points to.
+        target: PathBuf,
+    },
+}
+
+impl WriteAction {
+    /// The path this action creates.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::CreateFile(path) | Self::CreateDir(path) => path,
+            Self::CreateSymlink { path, .. } => path,
+        }
+    }
+}
+
+/// Options for [`FsTree::write_at_with_options`], decide what happens to paths that already exist.
+///
+/// By default, nothing is overwritten, and writing fails if any path already exists, like with
+/// [`FsTree::write_at`]. See [`FsTree::write_conflicts`] to check that beforehand.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteOptions {
+    overwrite_files: bool,
+    overwrite_dirs: bool,
+    overwrite_symlinks: bool,
+}
+
+impl WriteOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove existing regular files (and other non-directory files) in the way.
+    pub fn overwrite_files(mut self, arg: bool) -> Self {
+        self.overwrite_files = arg;
+        self
+    }
+
+    /// Reuse existing directories where directories are written, keeping their extra contents,
+    /// and remove them recursively where anything else is written.
+    pub fn overwrite_dirs(mut self, arg: bool) -> Self {
+        self.overwrite_dirs = arg;
+        self
+    }
+
+    /// Remove existing symlinks in the way, they are never followed.
+    pub fn overwrite_symlinks(mut self, arg: bool) -> Self {
+        self.overwrite_symlinks = arg;
+        self
+    }
+
+    fn overwrites_anything(&self) -> bool {
+        self.overwrite_files || self.overwrite_dirs || self.overwrite_symlinks
+    }
+}
+
 /// A filesystem tree recursive type.
 ///
 /// # Iterators:
@@ -55,20 +136,26 @@ impl FsTree {
     pub fn len_leafs(&self) -> usize {
         if let Some(children) = self.children() {
             children.values().map(Self::len_leafs).sum::<usize>()
-        } else if self.is_leaf() {
-            1
         } else {
-            0
+            1
         }
     }
 
     /// Calculate the length by counting all tree nodes, including the root.
     pub fn len_all(&self) -> usize {
-        if let Some(children) = self.children() {
-            children.values().map(Self::len_leafs).sum::<usize>()
-        } else {
-            1
-        }
+        let children_len = match self.children() {
+            Some(children) => children.values().map(Self::len_all).sum::<usize>(),
+            None => 0,
+        };
+
+        1 + children_len
+    }
+
+    /// Returns `true` if `self` is a directory with no children.
+    ///
+    /// Regular files and symlinks are never considered empty.
+    pub fn is_empty(&self) -> bool {
+        self.children().is_some_and(TrieMap::is_empty)
     }
 
     /// Construct a `FsTree` by reading from `path`, follows symlinks.
@@ -77,57 +164,123 @@ impl FsTree {
     ///
     /// # Errors:
     ///
+    /// - [`Error::NotFoundError`] if `path` doesn't exist.
     /// - If any IO error occurs.
     /// - If any file has an unexpected file type.
     ///
     /// [`symlink_read_at`]: FsTree::read_at
     pub fn read_at(path: impl AsRef<Path>) -> Result<Self> {
-        Self::__read_at(path.as_ref(), true)
+        Self::__read_at(
+            &mut path.as_ref().to_path_buf(),
+            true,
+            utils::MAX_SYMLINK_HOPS,
+            &mut vec![],
+        )
     }
 
     /// Construct a `FsTree` by reading from `path`.
     ///
+    /// Symlink targets are stored as returned by `read_link`, without any normalization, check
+    /// [`FsTree::symlink_read_at_with_resolution`] for other options.
+    ///
     /// If you don't want symlink-awareness, check [`read_at`].
     ///
     /// # Errors:
     ///
+    /// - [`Error::NotFoundError`] if `path` doesn't exist.
     /// - If any IO error occurs.
     /// - If any file has an unexpected file type.
     ///
     /// [`read_at`]: FsTree::symlink_read_at
     pub fn symlink_read_at(path: impl AsRef<Path>) -> Result<Self> {
-        Self::__read_at(path.as_ref(), false)
+        Self::__read_at(
+            &mut path.as_ref().to_path_buf(),
+            false,
+            utils::MAX_SYMLINK_HOPS,
+            &mut vec![],
+        )
     }
 
-    fn __read_at(path: &Path, follow_symlinks: bool) -> Result<Self> {
-        let get_file_type = if follow_symlinks {
-            FileType::read_at
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, returns `Ok(None)` if `path`
+    /// doesn't exist.
+    ///
+    /// Similar to [`Path::try_exists`], this saves you from matching [`io::ErrorKind::NotFound`]
+    /// when a missing root is expected.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs, except for a missing root.
+    /// - If any file has an unexpected file type.
+    pub fn try_read_at(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+
+        match fs::metadata(path) {
+            Ok(_) => Self::read_at(path).map(Some),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(utils::io_error_at(error, path)),
+        }
+    }
+
+    /// `ancestors` holds the ids of the directories being read, to detect loops when following
+    /// symlinks.
+    ///
+    /// The OS stops following at its own limit, so `max_symlink_hops` is only checked when lower.
+    pub(crate) fn __read_at(
+        path: &mut PathBuf,
+        follow_symlinks: bool,
+        max_symlink_hops: usize,
+        ancestors: &mut Vec<(u64, u64)>,
+    ) -> Result<Self> {
+        if follow_symlinks
+            && max_symlink_hops < utils::MAX_SYMLINK_HOPS
+            && utils::is_symlink_loop(path, max_symlink_hops)
+        {
+            return Err(Error::SymlinkLoopError(path.clone()));
+        }
+
+        let metadata = if follow_symlinks {
+            fs::metadata(&path).map_err(|error| utils::follow_error_at(error, path))?
         } else {
-            FileType::symlink_read_at
+            fs::symlink_metadata(&path).map_err(|error| utils::io_error_at(error, path))?
         };
 
-        match get_file_type(path)? {
+        match FileType::from(metadata.file_type()) {
             FileType::Regular => Ok(Self::Regular),
             FileType::Directory => {
+                // A symlink to a parent directory would be read forever
+                let id = utils::dir_id(&metadata).filter(|_| follow_symlinks);
+
+                if let Some(id) = id {
+                    if ancestors.contains(&id) {
+                        return Err(Error::SymlinkLoopError(path.clone()));
+                    }
+                    ancestors.push(id);
+                }
+
                 let mut children = TrieMap::new();
+                let entries =
+                    fs::read_dir(&path).map_err(|error| utils::io_error_at(error, path))?;
 
-                for entry in fs::read_dir(path)? {
-                    let entry = entry?;
-                    let entry_path = entry.path();
+                for entry in entries {
+                    let entry = entry.map_err(|error| utils::io_error_at(error, path))?;
+                    let name = PathBuf::from(entry.file_name());
 
-                    let node = Self::__read_at(&entry_path, follow_symlinks)?;
+                    // Reuse the same buffer for all paths, instead of allocating one per entry
+                    path.push(&name);
+                    let node = Self::__read_at(path, follow_symlinks, max_symlink_hops, ancestors);
+                    path.pop();
 
-                    let stripped_file_path = entry_path
-                        .strip_prefix(path)
-                        .expect("Failed to strip prefix, expected to always succeed in Linux");
+                    children.insert(name, node?);
+                }
 
-                    children.insert(stripped_file_path.into(), node);
+                if id.is_some() {
+                    ancestors.pop();
                 }
 
                 Ok(Self::Directory(children))
             },
             FileType::Symlink => {
-                let target_path = utils::follow_symlink(path)?;
+                let target_path = utils::follow_symlink(&path)?;
                 Ok(Self::Symlink(target_path))
             },
             other_type => {
@@ -139,6 +292,104 @@ impl FsTree {
         }
     }
 
+    /// Follow the symlink chain starting at `path`, returning the first path that isn't a symlink.
+    ///
+    /// Relative targets are resolved against the parent of the link that points to them.
+    ///
+    /// If `path` isn't a symlink, it's returned as is.
+    ///
+    /// # Errors:
+    ///
+    /// - If more than `max_hops` symlinks are followed, to guard against symlink loops.
+    /// - If the chain is broken, or any other IO error occurs.
+    pub fn follow_symlink_chain(path: impl AsRef<Path>, max_hops: usize) -> io::Result<PathBuf> {
+        let mut path = path.as_ref().to_path_buf();
+
+        for _ in 0..=max_hops {
+            if !fs::symlink_metadata(&path)?.is_symlink() {
+                return Ok(path);
+            }
+
+            let target = fs::read_link(&path)?;
+            path = match path.parent() {
+                Some(parent) => parent.join(target),
+                None => target,
+            };
+        }
+
+        Err(io::Error::other(format!(
+            "more than {max_hops} symlinks followed, possible loop"
+        )))
+    }
+
+    /// Follow the symlink chain starting at `path` to its final target.
+    ///
+    /// Same as [`FsTree::follow_symlink_chain`], with the same limit of hops Linux uses
+    /// (`SYMLOOP_MAX`, 40), so loops are reported instead of followed forever. The final target
+    /// isn't required to be a regular file, it's just the first path that isn't a symlink.
+    ///
+    /// # Errors:
+    ///
+    /// - If the chain loops, or is longer than the limit.
+    /// - If the chain is broken ([`io::ErrorKind::NotFound`]), or any other IO error occurs.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// // `/usr/bin/python3` -> `python3.12` -> ...
+    /// let interpreter = FsTree::resolve_symlink("/usr/bin/python3").unwrap();
+    /// println!("{}", interpreter.display());
+    /// ```
+    pub fn resolve_symlink(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        Self::follow_symlink_chain(path, utils::MAX_SYMLINK_HOPS)
+    }
+
+    /// Rewrite every symlink target into a canonical absolute path, as if `self` was at `base`.
+    ///
+    /// Relative targets are resolved against the parent of each link, then canonicalized by the
+    /// filesystem, so the links must exist on disk (at `base`). Absolute targets are canonicalized
+    /// too.
+    ///
+    /// Useful before [`FsTree::write_at`] when moving a tree to another location, so links keep
+    /// pointing to the same files.
+    ///
+    /// Returns the paths of the links whose targets couldn't be canonicalized (broken links),
+    /// their targets are left as is.
+    pub fn resolve_symlink_targets(&mut self, base: impl AsRef<Path>) -> Vec<PathBuf> {
+        let mut broken = vec![];
+        self.__resolve_symlink_targets(base.as_ref(), &mut PathBuf::new(), &mut broken);
+        broken
+    }
+
+    fn __resolve_symlink_targets(
+        &mut self,
+        base: &Path,
+        path: &mut PathBuf,
+        broken: &mut Vec<PathBuf>,
+    ) {
+        match self {
+            Self::Regular => {},
+            Self::Directory(children) => {
+                for (name, child) in children {
+                    path.push(name);
+                    child.__resolve_symlink_targets(base, path, broken);
+                    path.pop();
+                }
+            },
+            Self::Symlink(target) => {
+                let link = base.join(&path);
+                let link_dir = link.parent().unwrap_or(&link);
+
+                match fs::canonicalize(link_dir.join(&target)) {
+                    Ok(resolved) => *target = resolved,
+                    Err(_) => broken.push(path.clone()),
+                }
+            },
+        }
+    }
+
     /// Construct a structural copy of this `FsTree` by reading files at the given path.
     ///
     /// In other words, the returned tree is formed of all paths in `self` that are also found in
@@ -175,7 +426,9 @@ impl FsTree {
     ///
     /// # Errors:
     ///
-    /// - If an IO error happens, except [`io::ErrorKind::NotFound`]
+    /// - [`Error::NotFoundError`] if `path` doesn't exist.
+    /// - [`Error::UnexpectedFileTypeError`] if `path` isn't a regular file, directory or symlink.
+    /// - If an IO error happens, except [`io::ErrorKind::NotFound`] for paths inside of `path`.
     ///
     /// [`io::ErrorKind::NotFound`]: std::io::ErrorKind::NotFound
     pub fn read_structure_at(&self, path: impl AsRef<Path>) -> Result<Self> {
@@ -218,7 +471,9 @@ impl FsTree {
     ///
     /// # Errors:
     ///
-    /// - If an IO error happens, except [`io::ErrorKind::NotFound`]
+    /// - [`Error::NotFoundError`] if `path` doesn't exist.
+    /// - [`Error::UnexpectedFileTypeError`] if `path` isn't a regular file, directory or symlink.
+    /// - If an IO error happens, except [`io::ErrorKind::NotFound`] for paths inside of `path`.
     ///
     /// [`io::ErrorKind::NotFound`]: std::io::ErrorKind::NotFound
     pub fn symlink_read_structure_at(&self, path: impl AsRef<Path>) -> Result<Self> {
@@ -226,39 +481,69 @@ impl FsTree {
     }
 
     fn __read_structure_at(&self, folder: &Path, follow_symlinks: bool) -> Result<Self> {
-        let mut new_tree = FsTree::new_dir();
+        let mut path = folder.to_path_buf();
 
-        for relative_path in self.paths() {
-            // TODO: optimize this, instead of creating a PathBuf for each path,
-            // it's possible to use one mutable buffer with push + pop
-            let path = folder.join(&relative_path);
+        if let Some(tree) = self.__read_structure_node_at(&mut path, follow_symlinks)? {
+            return Ok(tree);
+        }
 
-            let get_file_type = if follow_symlinks {
-                FileType::read_at
-            } else {
-                FileType::symlink_read_at
-            };
+        // `None` for the root, either it's missing, or it has an unexpected file type
+        let file_type = if follow_symlinks {
+            FileType::read_at(&path)
+        } else {
+            FileType::symlink_read_at(&path)
+        };
 
-            let file_type = match get_file_type(&path) {
-                Ok(file_type) => file_type,
-                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
-                Err(err) => return Err(err.into()),
-            };
+        match file_type {
+            Ok(file_type) => Err(Error::UnexpectedFileTypeError(file_type, path)),
+            Err(err) => Err(utils::io_error_at(err, &path)),
+        }
+    }
 
-            let node = match file_type {
-                FileType::Regular => Self::Regular,
-                FileType::Directory => Self::new_dir(),
-                FileType::Symlink => {
-                    let target_path = utils::follow_symlink(&path)?;
-                    Self::Symlink(target_path)
-                },
-                _ => continue,
-            };
+    /// Returns `None` if there's nothing at `path`, or if it has an unexpected file type.
+    fn __read_structure_node_at(
+        &self,
+        path: &mut PathBuf,
+        follow_symlinks: bool,
+    ) -> Result<Option<Self>> {
+        let get_file_type = if follow_symlinks {
+            FileType::read_at
+        } else {
+            FileType::symlink_read_at
+        };
 
-            new_tree.insert(relative_path, node);
-        }
+        let file_type = match get_file_type(&path) {
+            Ok(file_type) => file_type,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(utils::io_error_at(err, path)),
+        };
+
+        let node = match file_type {
+            FileType::Regular => Self::Regular,
+            FileType::Directory => {
+                let mut children = TrieMap::new();
+
+                for (name, child) in self.children().into_iter().flatten() {
+                    // Reuse the same buffer for all paths, instead of allocating one per node
+                    path.push(name);
+                    let node = child.__read_structure_node_at(path, follow_symlinks);
+                    path.pop();
+
+                    if let Some(node) = node? {
+                        children.insert(name.clone(), node);
+                    }
+                }
+
+                Self::Directory(children)
+            },
+            FileType::Symlink => {
+                let target_path = utils::follow_symlink(&path)?;
+                Self::Symlink(target_path)
+            },
+            _ => return Ok(None),
+        };
 
-        Ok(new_tree)
+        Ok(Some(node))
     }
 
     /// Construct a `FsTree` from path pieces.
@@ -270,9 +555,7 @@ impl FsTree {
     /// # Warning
     ///
     /// The last piece is always a file, so inputs ending with `/`, like `Path::new("example/")` are
-    /// **NOT** parsed as directories.
-    ///
-    /// For my usage cases it's OK, but open an issue if you think otherwise 👍.
+    /// **NOT** parsed as directories, see [`FsTree::from_path_text_dir`] for that.
     ///
     /// # Examples:
     ///
@@ -302,6 +585,82 @@ impl FsTree {
         Self::from_path_pieces(path.as_ref().iter())
     }
 
+    /// Construct a `FsTree` from path pieces, like [`from_path_text`](FsTree::from_path_text),
+    /// but rejecting paths that could point outside of the directory the tree is written to.
+    ///
+    /// [`from_path_text`](FsTree::from_path_text) keeps `..` and `/` as regular pieces, so a
+    /// tree built from untrusted input, like `../../etc/passwd`, could make
+    /// [`FsTree::write_at`] create files anywhere. Use this instead when the path comes from
+    /// outside of the program.
+    ///
+    /// `.` components are skipped, so the resulting tree can always be written.
+    ///
+    /// # Errors:
+    ///
+    /// - [`PathError::ParentDir`] if the path has a `..` component.
+    /// - [`PathError::Absolute`] if the path is absolute, or has a prefix (on Windows).
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree, PathError};
+    ///
+    /// let result = FsTree::from_path_text_checked("a/b");
+    /// assert_eq!(result, Ok(tree! { a: { b } }));
+    ///
+    /// let result = FsTree::from_path_text_checked("../etc/passwd");
+    /// assert_eq!(result, Err(PathError::ParentDir("../etc/passwd".into())));
+    /// ```
+    pub fn from_path_text_checked(path: impl AsRef<Path>) -> std::result::Result<Self, PathError> {
+        let path = path.as_ref();
+
+        let mut pieces = vec![];
+
+        for component in path.components() {
+            match component {
+                Component::ParentDir => return Err(PathError::ParentDir(path.to_path_buf())),
+                Component::RootDir | Component::Prefix(_) => {
+                    return Err(PathError::Absolute(path.to_path_buf()))
+                },
+                Component::CurDir => {},
+                Component::Normal(piece) => pieces.push(piece),
+            }
+        }
+
+        Ok(Self::from_path_pieces(pieces))
+    }
+
+    /// Construct a `FsTree` from path pieces, like [`from_path_text`](FsTree::from_path_text),
+    /// but a trailing separator makes the last piece an empty directory.
+    ///
+    /// Without a trailing separator, the last piece is a regular file, as usual.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// assert_eq!(FsTree::from_path_text_dir("a/b/"), tree! { a: { b: {} } });
+    /// assert_eq!(FsTree::from_path_text_dir("a/b"), tree! { a: { b } });
+    /// ```
+    pub fn from_path_text_dir(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+
+        let ends_with_separator = path
+            .as_os_str()
+            .as_encoded_bytes()
+            .last()
+            .is_some_and(|&byte| std::path::is_separator(byte.into()));
+
+        if !ends_with_separator {
+            return Self::from_path_text(path);
+        }
+
+        path.iter().rev().fold(Self::new_dir(), |node, piece| {
+            Self::Directory(TrieMap::from([(piece.into(), node)]))
+        })
+    }
+
     /// Generic iterator version of [`from_path_text`](FsTree::from_path_text).
     pub fn from_path_pieces<I, P>(path_iter: I) -> Self
     where
@@ -318,587 +677,3921 @@ impl FsTree {
         }
     }
 
-    /// Creates an iterator that yields `(&FsTree, PathBuf)`.
+    /// Nest `self` under `prefix`, wrapping it in a chain of directories, one for each component.
     ///
-    /// See iterator docs at the [`iter` module documentation](crate::iter).
-    pub fn iter(&self) -> Iter {
-        Iter::new(self)
-    }
-
-    /// Creates an iterator that yields `&FsTree`.
+    /// Like [`from_path_text`](FsTree::from_path_text), but with `self` as the last node, `.`
+    /// components are ignored, and an empty prefix returns `self` unchanged.
     ///
-    /// See iterator docs at the [`iter` module documentation](crate::iter).
-    pub fn nodes(&self) -> NodesIter {
-        NodesIter::new(self)
-    }
-
-    /// Creates an iterator that yields `PathBuf`.
+    /// See [`FsTree::strip_prefix`] for the inverse.
     ///
-    /// See iterator docs at the [`iter` module documentation](crate::iter).
-    pub fn paths(&self) -> PathsIter {
-        PathsIter::new(self)
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     ".bashrc"
+    ///     ".config": {}
+    /// };
+    ///
+    /// let expected = tree! {
+    ///     home: {
+    ///         user: {
+    ///             ".bashrc"
+    ///             ".config": {}
+    ///         }
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(tree.prefix_with("home/user"), expected);
+    /// ```
+    pub fn prefix_with(self, prefix: impl AsRef<Path>) -> Self {
+        prefix
+            .as_ref()
+            .iter()
+            .rev()
+            .filter(|piece| *piece != OsStr::new("."))
+            .fold(self, |node, piece| {
+                Self::Directory(TrieMap::from([(piece.into(), node)]))
+            })
     }
 
-    /// Returns `true` if `self` type matches `other` type.
-    pub fn is_same_type_as(&self, other: &Self) -> bool {
-        mem::discriminant(self) == mem::discriminant(other)
+    /// Takes the subtree at `prefix`, rebasing the tree at a deeper point.
+    ///
+    /// The inverse of [`FsTree::prefix_with`]: `tree.prefix_with(prefix).strip_prefix(prefix)` is
+    /// always `Some(tree)`. Everything outside of `prefix` is dropped, to only borrow the subtree,
+    /// see [`FsTree::get`].
+    ///
+    /// `.` components are ignored, and an empty prefix returns `self`. Returns `None` if `prefix`
+    /// isn't in the tree, or goes through a non-directory.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     home: {
+    ///         user: {
+    ///             ".bashrc"
+    ///         }
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(tree.clone().strip_prefix("home/user"), Some(tree! { ".bashrc" }));
+    /// assert_eq!(tree.strip_prefix("home/other"), None);
+    /// ```
+    pub fn strip_prefix(self, prefix: impl AsRef<Path>) -> Option<Self> {
+        prefix
+            .as_ref()
+            .iter()
+            .filter(|piece| *piece != OsStr::new("."))
+            .try_fold(self, |node, piece| {
+                match node {
+                    Self::Directory(mut children) => children.remove(Path::new(piece)),
+                    _ => None,
+                }
+            })
     }
 
-    /// Returns `Ok(true)` if all nodes exist in the filesystem.
+    /// Construct a `FsTree` by merging many [`from_path_text`](FsTree::from_path_text) paths.
     ///
-    /// # Errors:
+    /// Intermediate components are directories and final components are regular files, unless
+    /// another path goes through them, e.g. `a/b` and `a/b/c` make `b` a directory regardless of
+    /// the order they appear. Duplicated paths and shared prefixes are merged, empty paths are
+    /// ignored.
     ///
-    /// Similar to how [`Path::try_exists`] works, this function returns `false` if any IO error
-    /// occurred when checking [`std::fs::symlink_metadata`] (except [`io::ErrorKind::NotFound`]).
-    pub fn try_exists(&mut self) -> io::Result<bool> {
-        for path in self.paths() {
-            match fs::symlink_metadata(path) {
-                Ok(_) => continue,
-                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
-                Err(error) => return Err(error),
-            }
-        }
+    /// Also available through [`FromIterator`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let result = FsTree::from_paths(["a/b/c", "a/d", "a/b", "e"]);
+    ///
+    /// let expected = tree! {
+    ///     a: {
+    ///         b: {
+    ///             c
+    ///         }
+    ///         d
+    ///     }
+    ///     e
+    /// };
+    ///
+    /// assert_eq!(result, expected);
+    /// ```
+    pub fn from_paths(paths: impl IntoIterator<Item = impl AsRef<Path>>) -> Self {
+        let mut tree = Self::new_dir();
 
-        Ok(true)
-    }
+        for path in paths {
+            let mut components = path.as_ref().iter();
 
-    /// Merge two trees.
-    ///
-    /// When conflicts happen, entries from `self` are kept, and the `other`'s are discarded.
-    pub fn merge(self, other: Self) -> Self {
-        // let's merge the right (consuming) onto the left (mutating)
-        let mut left = self;
-        let right = other;
+            let Some(file_name) = components.next_back() else {
+                continue;
+            };
 
-        match (&mut left, right) {
-            // both a directory at the same path, try merging
-            (FsTree::Directory(left_children), FsTree::Directory(right_children)) => {
-                for (path, right_node) in right_children {
-                    // if right node exists, remove, merge and re-add, otherwise, just add it
-                    if let Some(left_node) = left_children.remove(&path) {
-                        let new_node = left_node.merge(right_node);
-                        left_children.insert(path, new_node);
-                    } else {
-                        left_children.insert(path, right_node);
-                    }
-                }
-            },
-            (_, _) => { /* conflict, but nothing to do, don't mutate left side */ },
+            tree.__dir_children_mut(components)
+                .entry(PathBuf::from(file_name))
+                .or_insert(Self::Regular);
         }
 
-        left
+        tree
     }
 
-    /// Checks for conflicts in case the two trees would be merged.
+    /// The longest path prefix shared by all `paths`, compared component-wise.
     ///
-    /// Also see [`Self::merge`].
-    pub fn conflicts_with(&self, other: &Self) -> bool {
-        let mut left = self;
-        let right = other;
+    /// Useful for picking a single root to read a tree that covers all inputs.
+    ///
+    /// Returns `None` if `paths` is empty or if they share no component.
+    ///
+    /// This is purely lexical, the filesystem isn't checked, so if all paths are the same (or
+    /// there's only one), that path is returned as is, even if it's a file. Use [`Path::parent`]
+    /// on the result if you need a directory in that case.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use fs_tree::FsTree;
+    ///
+    /// let paths = [Path::new("/home/user/a"), Path::new("/home/user/b/c")];
+    /// assert_eq!(FsTree::common_root(paths), Some(PathBuf::from("/home/user")));
+    ///
+    /// // Component-wise, not character-wise
+    /// let paths = [Path::new("/home/user1"), Path::new("/home/user2")];
+    /// assert_eq!(FsTree::common_root(paths), Some(PathBuf::from("/home")));
+    ///
+    /// // A single path is returned as is, file or not
+    /// let paths = [Path::new("/home/user/file.txt")];
+    /// assert_eq!(FsTree::common_root(paths), Some(PathBuf::from("/home/user/file.txt")));
+    /// ```
+    pub fn common_root<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf> {
+        let mut paths = paths.into_iter();
+        let mut common: Vec<Component> = paths.next()?.components().collect();
 
-        match (&mut left, right) {
-            (FsTree::Directory(left_children), FsTree::Directory(right_children)) => {
-                for (path, right_node) in right_children {
-                    if let Some(left_node) = left_children.get(path.as_path()) {
-                        if left_node.conflicts_with(right_node) {
-                            return true;
-                        }
-                    }
-                }
-            },
-            (_, _) => return true,
+        for path in paths {
+            let shared = common
+                .iter()
+                .zip(path.components())
+                .take_while(|(a, b)| **a == *b)
+                .count();
+
+            common.truncate(shared);
         }
 
-        false
+        if common.is_empty() {
+            None
+        } else {
+            Some(common.iter().collect())
+        }
     }
 
-    /// Reference to children if `self.is_directory()`.
-    pub fn children(&self) -> Option<&TrieMap> {
-        match &self {
-            Self::Directory(children) => Some(children),
-            _ => None,
+    /// Returns the smallest set of paths whose subtrees cover exactly the selected `paths`.
+    ///
+    /// A selected directory covers its whole subtree, and a directory whose children are all
+    /// covered is collapsed into itself, useful for generating concise include lists (for an
+    /// archive, for example) from a selection.
+    ///
+    /// The result is sorted, paths not present in the tree are ignored, and if the whole tree is
+    /// covered, the result is a single empty path (the root).
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     a: {
+    ///         b
+    ///         c
+    ///     }
+    ///     d: {
+    ///         e
+    ///         f
+    ///     }
+    /// };
+    ///
+    /// let selection = ["a/b", "a/c", "d/e"].map(Path::new);
+    /// assert_eq!(tree.minimal_cover(selection), [PathBuf::from("a"), PathBuf::from("d/e")]);
+    /// ```
+    pub fn minimal_cover<'a>(&self, paths: impl IntoIterator<Item = &'a Path>) -> Vec<PathBuf> {
+        let selected: BTreeSet<&Path> = paths.into_iter().collect();
+        let mut cover = vec![];
+
+        if self.__minimal_cover(&selected, &mut PathBuf::new(), &mut cover) {
+            return vec![PathBuf::new()];
         }
+
+        cover.sort();
+        cover
     }
 
-    /// Mutable reference to children if `self.is_directory()`.
-    pub fn children_mut(&mut self) -> Option<&mut TrieMap> {
-        match self {
-            Self::Directory(children) => Some(children),
-            _ => None,
+    /// Returns `true` if `self` is entirely covered, otherwise, pushes the covered descendants.
+    fn __minimal_cover(
+        &self,
+        selected: &BTreeSet<&Path>,
+        path: &mut PathBuf,
+        cover: &mut Vec<PathBuf>,
+    ) -> bool {
+        if selected.contains(path.as_path()) {
+            return true;
         }
-    }
 
-    /// Reference to target path, if `self.is_symlink()`.
-    pub fn target(&self) -> Option<&Path> {
-        match &self {
-            Self::Symlink(target_path) => Some(target_path),
-            _ => None,
+        let Some(children) = self.children().filter(|children| !children.is_empty()) else {
+            return false;
+        };
+
+        let mut covered_children = vec![];
+
+        for (name, child) in children {
+            path.push(name);
+
+            if child.__minimal_cover(selected, path, cover) {
+                covered_children.push(path.clone());
+            }
+
+            path.pop();
+        }
+
+        if covered_children.len() == children.len() {
+            true
+        } else {
+            cover.extend(covered_children);
+            false
         }
     }
 
-    /// Mutable reference to target path, if `self.is_symlink()`.
-    pub fn target_mut(&mut self) -> Option<&mut PathBuf> {
-        match self {
-            Self::Symlink(target_path) => Some(target_path),
-            _ => None,
+    /// Construct a `FsTree` from `(path, kind)` entries, in any order, without touching the
+    /// filesystem.
+    ///
+    /// Useful for sources other than the filesystem, like archives or test fixtures. Missing
+    /// parent directories are created, so only the leaves are required.
+    ///
+    /// When entries collide, later entries win, except that a directory entry keeps the children
+    /// of an existing directory, and a non-directory in the way of a path is replaced by a
+    /// directory. `.` components and empty paths are ignored.
+    ///
+    /// Unlike [`FsTree::from_sorted_pairs`], the order doesn't matter, and this never panics.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree, NodeKind};
+    ///
+    /// let entries = [
+    ///     ("src/lib.rs".into(), NodeKind::Regular),
+    ///     ("docs".into(), NodeKind::Directory),
+    ///     ("src/link".into(), NodeKind::Symlink("lib.rs".into())),
+    /// ];
+    ///
+    /// let expected = tree! {
+    ///     src: {
+    ///         "lib.rs"
+    ///         link -> "lib.rs"
+    ///     }
+    ///     docs: {}
+    /// };
+    ///
+    /// assert_eq!(FsTree::from_entries(entries), expected);
+    /// ```
+    pub fn from_entries(entries: impl IntoIterator<Item = (PathBuf, NodeKind)>) -> Self {
+        let mut tree = Self::new_dir();
+
+        for (path, kind) in entries {
+            let mut components = path.iter().filter(|piece| *piece != OsStr::new("."));
+
+            let Some(file_name) = components.next_back() else {
+                continue;
+            };
+
+            let children = tree.__dir_children_mut(components);
+            let name = PathBuf::from(file_name);
+
+            match kind {
+                NodeKind::Directory => {
+                    let node = children.entry(name).or_insert_with(Self::new_dir);
+                    if !node.is_dir() {
+                        *node = Self::new_dir();
+                    }
+                },
+                kind => {
+                    children.insert(name, kind.into());
+                },
+            }
         }
+
+        tree
     }
 
-    // /// Apply a closure for each direct child of this FsTree.
-    // ///
-    // /// Only 1 level deep.
-    // pub fn apply_to_children0(&mut self, f: impl FnMut(&mut Self)) {
-    //     if let Some(children) = self.children_mut() {
-    //         children.iter_mut().for_each(f);
-    //     }
-    // }
+    /// Returns the children of the directory at `path`, creating the missing directories, and
+    /// replacing the non-directories in the way.
+    ///
+    /// `self` must be a directory.
+    fn __dir_children_mut<'a>(&mut self, path: impl Iterator<Item = &'a OsStr>) -> &mut TrieMap {
+        let mut children = self.children_mut().expect("self is a directory");
 
-    // /// Apply a closure to all direct and indirect descendants inside of this structure.
-    // ///
-    // /// Calls recursively for all levels.
-    // pub fn apply_to_all_children1(&mut self, f: impl FnMut(&mut Self) + Copy) {
-    //     if let Some(children) = self.children_mut() {
-    //         children
-    //             .iter_mut()
-    //             .for_each(|x| x.apply_to_all_children1(f));
-    //         children.iter_mut().for_each(f);
-    //     }
-    // }
+        for component in path {
+            let node = children
+                .entry(PathBuf::from(component))
+                .or_insert_with(Self::new_dir);
 
-    // /// Apply a closure to all direct and indirect descendants inside (including root).
-    // ///
-    // /// Calls recursively for all levels.
-    // pub fn apply_to_all(&mut self, mut f: impl FnMut(&mut Self) + Copy) {
-    //     f(self);
-    //     if let Some(children) = self.children_mut() {
-    //         for child in children.iter_mut() {
-    //             child.apply_to_all(f);
-    //         }
-    //     }
-    // }
+            // A file that is a prefix of this path becomes a directory
+            if !node.is_dir() {
+                *node = Self::new_dir();
+            }
 
-    /// Returns `true` if `self` is a leaf node.
+            children = node
+                .children_mut()
+                .expect("just made sure it's a directory");
+        }
+
+        children
+    }
+
+    /// Construct a `FsTree` from `(path, kind)` pairs sorted by path.
     ///
-    /// A leaf node might be of any type, including directory, however, a
-    /// non-leaf node is always a directory.
-    pub fn is_leaf(&self) -> bool {
-        match self {
-            Self::Regular | Self::Symlink(_) => true,
-            Self::Directory(children) => children.is_empty(),
+    /// This is the inverse of [`FsTree::into_sorted_pairs`].
+    ///
+    /// # Panics:
+    ///
+    /// - If a path comes before its parent directory, see [`FsTree::insert`].
+    pub fn from_sorted_pairs(pairs: impl IntoIterator<Item = (PathBuf, NodeKind)>) -> Self {
+        let mut tree = Self::new_dir();
+
+        for (path, kind) in pairs {
+            tree.insert(path, kind.into());
         }
+
+        tree
     }
 
-    /// The variant string, useful for showing to user.
-    pub fn variant_str(&self) -> &'static str {
+    /// Consumes the tree, returning each `(path, kind)` pair, sorted by path.
+    ///
+    /// The root is included with an empty path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree, NodeKind};
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    ///     link -> target
+    /// };
+    ///
+    /// let pairs = tree.clone().into_sorted_pairs();
+    ///
+    /// assert_eq!(pairs, [
+    ///     ("".into(), NodeKind::Directory),
+    ///     ("dir".into(), NodeKind::Directory),
+    ///     ("dir/file".into(), NodeKind::Regular),
+    ///     ("link".into(), NodeKind::Symlink("target".into())),
+    /// ]);
+    ///
+    /// assert_eq!(FsTree::from_sorted_pairs(pairs), tree);
+    /// ```
+    pub fn into_sorted_pairs(self) -> Vec<(PathBuf, NodeKind)> {
+        let mut pairs = vec![];
+        self.__into_sorted_pairs(PathBuf::new(), &mut pairs);
+        pairs
+    }
+
+    // DFS over the sorted `TrieMap`s already yields paths in sorted order, because `Path`s are
+    // compared component-wise
+    fn __into_sorted_pairs(self, path: PathBuf, pairs: &mut Vec<(PathBuf, NodeKind)>) {
         match self {
-            Self::Regular => "regular file",
-            Self::Directory(_) => "directory",
-            Self::Symlink(_) => "symlink",
+            Self::Regular => pairs.push((path, NodeKind::Regular)),
+            Self::Symlink(target) => pairs.push((path, NodeKind::Symlink(target))),
+            Self::Directory(children) => {
+                pairs.push((path.clone(), NodeKind::Directory));
+
+                for (name, child) in children {
+                    child.__into_sorted_pairs(path.join(name), pairs);
+                }
+            },
         }
     }
 
-    /// Returns `true` if self matches the [`FsTree::Regular`] variant.
-    pub fn is_regular(&self) -> bool {
-        matches!(self, Self::Regular)
+    /// Creates an iterator that yields `(&FsTree, PathBuf)`.
+    ///
+    /// See iterator docs at the [`iter` module documentation](crate::iter).
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(self)
     }
 
-    /// Returns `true` if self matches the [`FsTree::Directory`] variant.
-    pub fn is_dir(&self) -> bool {
-        matches!(self, Self::Directory(_))
+    /// Creates an iterator that yields `&FsTree`.
+    ///
+    /// See iterator docs at the [`iter` module documentation](crate::iter).
+    pub fn nodes(&self) -> NodesIter<'_> {
+        NodesIter::new(self)
     }
 
-    /// Returns `true` if self matches the [`FsTree::Symlink`] variant.
-    pub fn is_symlink(&self) -> bool {
-        matches!(self, Self::Symlink(_))
+    /// Creates an iterator that yields `&mut FsTree` for each regular file and symlink.
+    ///
+    /// Directories aren't yielded, see [`NodesIterMut`].
+    ///
+    /// See iterator docs at the [`iter` module documentation](crate::iter).
+    pub fn nodes_mut(&mut self) -> NodesIterMut<'_> {
+        NodesIterMut::new(self)
     }
 
-    // /// Generate a diff from two different trees.
-    // pub fn diff(&self, other: &Self) {
-    //     if !self.has_same_type_as(other) {
-    //         println!("Types differ! ");
-    //     }
+    /// Creates a consuming iterator that yields owned `FsTree`s, in the same order as
+    /// [`FsTree::nodes`].
+    ///
+    /// Directories are yielded empty, because their children are moved out and yielded after
+    /// them, see [`IntoNodesIter`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         link -> target
+    ///     }
+    /// };
+    ///
+    /// // The targets are moved out, without cloning
+    /// let targets: Vec<PathBuf> = tree
+    ///     .into_nodes()
+    ///     .filter_map(|node| match node {
+    ///         FsTree::Symlink(target) => Some(target),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    ///
+    /// assert_eq!(targets, [PathBuf::from("target")]);
+    /// ```
+    pub fn into_nodes(self) -> IntoNodesIter {
+        IntoNodesIter::new(self)
+    }
 
-    //     let (self_children, other_children) = match (&self.file_type, &other.file_type) {
-    //         (Self::Directory(self_children), Self::Directory(other_children)) => {
-    //             (self_children, other_children)
-    //         },
-    //         _ => panic!(),
-    //     };
+    /// Creates an iterator that yields `PathBuf`.
+    ///
+    /// See iterator docs at the [`iter` module documentation](crate::iter).
+    pub fn paths(&self) -> PathsIter<'_> {
+        PathsIter::new(self)
+    }
 
-    //     let mut lookup = self_children
-    //         .iter()
-    //         .map(|x| (&x.path, x))
-    //         .collect::<HashMap<&PathBuf, &FsTree>>();
+    /// Creates an iterator that yields `(&Path, &FsTree)`, the path is just the node name.
+    ///
+    /// The names are borrowed from the tree, so, unlike [`FsTree::paths`], this doesn't allocate,
+    /// useful when you only need the last component, like when checking file extensions.
+    ///
+    /// See iterator docs at the [`iter` module documentation](crate::iter).
+    pub fn nodes_with_names(&self) -> NamedNodesIter<'_> {
+        NamedNodesIter::new(self)
+    }
 
-    //     for other_child in other_children {
-    //         if let Some(self_child) = lookup.remove(&other_child.path) {
-    //             if self_child.has_same_type_as(other_child) {
-    //                 if self_child.is_dir() {
-    //                     self_child.diff(other_child);
-    //                 }
-    //             } else {
-    //                 println!(
-    //                     "File {:?} is a {} while file {:?} is a {}",
-    //                     self_child.path,
-    //                     self_child.file_type.file_type_display(),
-    //                     other_child.path,
-    //                     other_child.file_type.file_type_display(),
-    //                 );
-    //             }
-    //         } else {
-    //             let path = &other_child.path;
-    //             println!(
-    //                 "2Only in {:?}: {:?}",
-    //                 path.parent().unwrap(),
-    //                 path.file_name().unwrap()
-    //             );
-    //         }
-    //     }
-
-    //     for child_left in lookup.values() {
-    //         let path = &child_left.path;
-    //         println!(
-    //             "1Only in {:?}: {:?}",
-    //             path.parent().unwrap(),
-    //             path.file_name().unwrap()
-    //         );
-    //     }
-    // }
+    /// Creates an iterator that yields `PathBuf`, in post-order (children before their parents).
+    ///
+    /// This is the order for deleting files, like with `rm -r`.
+    ///
+    /// See iterator docs at the [`iter` module documentation](crate::iter).
+    pub fn paths_post_order(&self) -> PostOrderPathsIter<'_> {
+        PostOrderPathsIter::new(self)
+    }
 
-    /// Write the tree structure in the path.
+    /// Creates an iterator that yields `(PathBuf, &Path)` for each symlink, with its target.
     ///
-    /// # Errors:
+    /// Symlinks are yielded in the same order as [`FsTree::iter`], if `self` is a symlink, it's
+    /// yielded with an empty path.
     ///
-    /// - If provided folder doesn't exist, or is not a directory.
-    /// - If any other IO error occurs.
-    pub fn write_at(&self, folder: impl AsRef<Path>) -> Result<()> {
-        let folder = folder.as_ref();
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     ".bashrc" -> "dotfiles/bashrc"
+    ///     ".config": {
+    ///         nvim -> "../dotfiles/nvim"
+    ///     }
+    /// };
+    ///
+    /// let symlinks: Vec<(PathBuf, &Path)> = tree.symlinks().collect();
+    ///
+    /// assert_eq!(symlinks, [
+    ///     (".bashrc".into(), Path::new("dotfiles/bashrc")),
+    ///     (".config/nvim".into(), Path::new("../dotfiles/nvim")),
+    /// ]);
+    /// ```
+    pub fn symlinks(&self) -> impl Iterator<Item = (PathBuf, &Path)> {
+        self.iter()
+            .skip_regular_files(true)
+            .skip_dirs(true)
+            .filter_map(|(node, path)| Some((path, node.target()?)))
+    }
 
-        #[cfg(feature = "fs-err")]
-        let symlink_function = fs_err::os::unix::fs::symlink;
-        #[cfg(not(feature = "fs-err"))]
-        let symlink_function = std::os::unix::fs::symlink;
+    /// Pushes the paths of the tree to `out`, in the same order as [`FsTree::paths`].
+    ///
+    /// Equivalent to `out.extend(tree.paths())`, useful for aggregating the paths of many trees
+    /// in the same collection.
+    pub fn extend_paths_into(&self, out: &mut Vec<PathBuf>) {
+        self.__extend_paths_into(&mut PathBuf::new(), out);
+    }
 
-        for (node, path) in self.iter().skip(1) {
-            let path = folder.join(&path);
+    fn __extend_paths_into(&self, path: &mut PathBuf, out: &mut Vec<PathBuf>) {
+        out.push(path.clone());
 
-            match &node {
-                Self::Regular => {
-                    fs::File::create(path)?;
-                },
-                Self::Directory(_) => {
-                    fs::create_dir(path)?;
-                },
-                Self::Symlink(target) => {
-                    symlink_function(target, path)?;
-                },
+        if let Some(children) = self.children() {
+            for (name, child) in children {
+                path.push(name);
+                child.__extend_paths_into(path, out);
+                path.pop();
             }
         }
-
-        Ok(())
     }
 
-    /// Returns a reference to the node at the path, if any.
+    /// Visit nodes in DFS order, alongside their paths, letting `f` skip subtrees or stop early.
     ///
-    /// # Errors:
+    /// The root is visited first with an empty path. After each node, `f` returns a
+    /// [`WalkAction`] to decide how the walk goes on, skipped subtrees aren't traversed at all,
+    /// which makes this cheaper than filtering an iterator when searching with pruning.
     ///
-    /// - Returns `None` if there is no node at the given path.
+    /// Returns `true` if the walk was stopped by [`WalkAction::Stop`].
     ///
     /// # Examples:
     ///
     /// ```
-    /// use fs_tree::FsTree;
+    /// use std::path::{Path, PathBuf};
     ///
-    /// let root = FsTree::from_path_text("a/b/c");
+    /// use fs_tree::{tree, WalkAction};
     ///
-    /// // Indexing is relative from `root`, so `root` cannot be indexed.
-    /// assert_eq!(root, FsTree::from_path_text("a/b/c"));
-    /// assert_eq!(root["a"], FsTree::from_path_text("b/c"));
-    /// assert_eq!(root["a/b"], FsTree::from_path_text("c"));
-    /// assert_eq!(root["a"]["b"], FsTree::from_path_text("c"));
-    /// assert_eq!(root["a/b/c"], FsTree::Regular);
-    /// assert_eq!(root["a/b"]["c"], FsTree::Regular);
-    /// assert_eq!(root["a"]["b/c"], FsTree::Regular);
-    /// assert_eq!(root["a"]["b"]["c"], FsTree::Regular);
+    /// let tree = tree! {
+    ///     build: {
+    ///         "Cargo.toml"
+    ///     }
+    ///     crates: {
+    ///         a: {
+    ///             "Cargo.toml"
+    ///         }
+    ///         b: {
+    ///             "Cargo.toml"
+    ///         }
+    ///     }
+    /// };
+    ///
+    /// // Find the first manifest, ignoring `build`
+    /// let mut found = None;
+    /// tree.walk(|path, _| {
+    ///     if path == Path::new("build") {
+    ///         WalkAction::SkipSubtree
+    ///     } else if path.ends_with("Cargo.toml") {
+    ///         found = Some(path.to_path_buf());
+    ///         WalkAction::Stop
+    ///     } else {
+    ///         WalkAction::Continue
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(found, Some(PathBuf::from("crates/a/Cargo.toml")));
     /// ```
-    pub fn get(&self, path: impl AsRef<Path>) -> Option<&Self> {
-        let path = path.as_ref();
+    pub fn walk(&self, mut f: impl FnMut(&Path, &Self) -> WalkAction) -> bool {
+        self.__walk(&mut PathBuf::new(), &mut f)
+    }
 
-        // Split first piece from the rest
-        let (popped, path_rest) = {
-            let mut iter = path.iter();
-            let popped: Option<&Path> = iter.next().map(OsStr::as_ref);
-            (popped, iter.as_path())
-        };
+    /// Returns `true` if stopped.
+    fn __walk(&self, path: &mut PathBuf, f: &mut impl FnMut(&Path, &Self) -> WalkAction) -> bool {
+        match f(path, self) {
+            WalkAction::Continue => {},
+            WalkAction::SkipSubtree => return false,
+            WalkAction::Stop => return true,
+        }
 
-        // If path ended, we reached the desired node
-        let Some(popped) = popped else {
-            return Some(self);
+        let Some(children) = self.children() else {
+            return false;
         };
 
-        // Corner case: if `.`, ignore it and call again with the rest
-        if popped == Path::new(".") {
-            return self.get(path_rest);
+        for (name, child) in children {
+            path.push(name);
+            let stopped = child.__walk(path, f);
+            path.pop();
+
+            if stopped {
+                return true;
+            }
         }
 
-        self.children()?
-            .get(popped)
-            .and_then(|child| child.get(path_rest))
+        false
     }
 
-    /// Returns a mutable reference to the node at the path, if any.
+    /// Visit every node in DFS order, alongside its full path and its parent directory.
     ///
-    /// This is the mutable version of [`FsTree::get`].
-    pub fn get_mut(&mut self, path: impl AsRef<Path>) -> Option<&mut Self> {
-        let path = path.as_ref();
+    /// The parent is `None` for the root, which is visited first with an empty path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    /// };
+    ///
+    /// tree.walk_with_parent(|parent, path, _node| {
+    ///     if let Some(parent) = parent {
+    ///         assert_eq!(parent, &tree[path.parent().unwrap()]);
+    ///     }
+    /// });
+    /// ```
+    pub fn walk_with_parent(&self, mut f: impl FnMut(Option<&Self>, &Path, &Self)) {
+        f(None, Path::new(""), self);
+        self.__walk_with_parent(&mut PathBuf::new(), &mut f);
+    }
 
-        // Split first piece from the rest
-        let (popped, path_rest) = {
-            let mut iter = path.iter();
-            let popped: Option<&Path> = iter.next().map(OsStr::as_ref);
-            (popped, iter.as_path())
+    fn __walk_with_parent(
+        &self,
+        path: &mut PathBuf,
+        f: &mut impl FnMut(Option<&Self>, &Path, &Self),
+    ) {
+        let Some(children) = self.children() else {
+            return;
         };
 
-        // If path ended, we reached the desired node
-        let Some(popped) = popped else {
-            return Some(self);
+        for (name, child) in children {
+            path.push(name);
+            f(Some(self), path, child);
+            child.__walk_with_parent(path, f);
+            path.pop();
+        }
+    }
+
+    /// Visit every node in DFS order, alongside its path as a slice of components.
+    ///
+    /// The components are borrowed from the tree and kept in one reused buffer, so unlike
+    /// [`FsTree::paths`], this doesn't allocate a `PathBuf` for each node.
+    ///
+    /// The root is visited first with an empty slice.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    /// };
+    ///
+    /// let mut visited = vec![];
+    /// tree.for_each_with_components(|components, _node| {
+    ///     visited.push(components.join("/".as_ref()));
+    /// });
+    ///
+    /// assert_eq!(visited, ["", "dir", "dir/file"]);
+    /// ```
+    pub fn for_each_with_components(&self, mut f: impl FnMut(&[&OsStr], &Self)) {
+        self.__for_each_with_components(&mut vec![], &mut f);
+    }
+
+    fn __for_each_with_components<'a>(
+        &'a self,
+        components: &mut Vec<&'a OsStr>,
+        f: &mut impl FnMut(&[&OsStr], &Self),
+    ) {
+        f(components, self);
+
+        if let Some(children) = self.children() {
+            for (name, child) in children {
+                components.push(name.as_os_str());
+                child.__for_each_with_components(components, f);
+                components.pop();
+            }
+        }
+    }
+
+    /// Visit the path of every node in DFS order, the allocation-free alternative to
+    /// [`FsTree::paths`].
+    ///
+    /// Paths are built in one reused buffer and lent to `f`, so unlike iterators, which can't
+    /// lend from themselves and must allocate a `PathBuf` for each node, this only allocates when
+    /// the buffer grows. Clone the path if you need to keep it.
+    ///
+    /// Yields the same paths, in the same order, as [`FsTree::paths`], the root is visited first
+    /// with an empty path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         "file.rs"
+    ///     }
+    ///     other
+    /// };
+    ///
+    /// let mut rust_files = 0;
+    /// tree.for_each_path(|path| {
+    ///     if path.extension().is_some_and(|extension| extension == "rs") {
+    ///         rust_files += 1;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(rust_files, 1);
+    /// ```
+    pub fn for_each_path(&self, mut f: impl FnMut(&Path)) {
+        self.__for_each_path(&mut PathBuf::new(), &mut f);
+    }
+
+    fn __for_each_path(&self, path: &mut PathBuf, f: &mut impl FnMut(&Path)) {
+        f(path);
+
+        if let Some(children) = self.children() {
+            for (name, child) in children {
+                path.push(name);
+                child.__for_each_path(path, f);
+                path.pop();
+            }
+        }
+    }
+
+    /// Returns `true` if `self` type matches `other` type.
+    pub fn is_same_type_as(&self, other: &Self) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    /// Returns `true` if both trees have the same layout and node types, ignoring symlink targets.
+    ///
+    /// Unlike `==`, which compares symlink targets too, any two symlinks are considered equal.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let left = tree! { dir: { link -> a } };
+    /// let right = tree! { dir: { link -> b } };
+    ///
+    /// assert!(left.structural_eq(&right));
+    /// assert_ne!(left, right);
+    /// ```
+    pub fn structural_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Directory(left), Self::Directory(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|((left_name, left), (right_name, right))| {
+                            left_name == right_name && left.structural_eq(right)
+                        })
+            },
+            (left, right) => left.is_same_type_as(right),
+        }
+    }
+
+    /// Alias of [`FsTree::structural_eq`].
+    ///
+    /// Returns `true` if both trees have the same set of paths, and the nodes at each path have
+    /// the same variant, symlink targets are ignored. See [`FsTree::is_same_type_as`] to only
+    /// compare the roots.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! { dir: { leaf } };
+    ///
+    /// assert!(tree.same_shape_as(&tree! { dir: { leaf } }));
+    /// assert!(!tree.same_shape_as(&tree! { dir: { leaf: {} } }));
+    /// assert!(!tree.same_shape_as(&tree! { dir: { leaf -> target } }));
+    /// ```
+    pub fn same_shape_as(&self, other: &Self) -> bool {
+        self.structural_eq(other)
+    }
+
+    /// Returns `Ok(true)` if all nodes exist in the filesystem.
+    ///
+    /// # Errors:
+    ///
+    /// Similar to how [`Path::try_exists`] works, this function returns an error if any IO error
+    /// occurred when checking [`std::fs::symlink_metadata`] (except [`io::ErrorKind::NotFound`]),
+    /// the error carries the path that failed.
+    pub fn try_exists(&self) -> Result<bool> {
+        for path in self.paths() {
+            match fs::symlink_metadata(&path) {
+                Ok(_) => continue,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+                Err(error) => return Err(utils::io_error_at(error, &path)),
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Checks that the tree has no symlinks.
+    ///
+    /// # Errors:
+    ///
+    /// - Returns the paths of all symlinks found, in DFS order.
+    pub fn assert_symlink_free(&self) -> std::result::Result<(), Vec<PathBuf>> {
+        let symlinks: Vec<PathBuf> = self
+            .iter()
+            .skip_regular_files(true)
+            .skip_dirs(true)
+            .map(|(_, path)| path)
+            .collect();
+
+        if symlinks.is_empty() {
+            Ok(())
+        } else {
+            Err(symlinks)
+        }
+    }
+
+    /// Checks that all paths in the tree, and all symlink targets, are valid UTF-8.
+    ///
+    /// Useful for failing early before handing the tree to something that only supports UTF-8,
+    /// like some serializers.
+    ///
+    /// # Errors:
+    ///
+    /// - Returns the paths of all nodes whose path or symlink target isn't valid UTF-8, in DFS
+    ///   order, the descendants of an invalid directory name are included.
+    pub fn all_paths_utf8(&self) -> std::result::Result<(), Vec<PathBuf>> {
+        let invalid: Vec<PathBuf> = self
+            .iter()
+            .filter(|(node, path)| {
+                path.to_str().is_none()
+                    || node
+                        .target()
+                        .is_some_and(|target| target.to_str().is_none())
+            })
+            .map(|(_, path)| path)
+            .collect();
+
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid)
+        }
+    }
+
+    /// Merge two trees.
+    ///
+    /// When conflicts happen, entries from `self` are kept, and the `other`'s are discarded, see
+    /// [`FsTree::try_merge`] to get the conflicts reported instead.
+    pub fn merge(self, other: Self) -> Self {
+        // let's merge the right (consuming) onto the left (mutating)
+        let mut left = self;
+        let right = other;
+
+        match (&mut left, right) {
+            // both a directory at the same path, try merging
+            (FsTree::Directory(left_children), FsTree::Directory(right_children)) => {
+                for (path, right_node) in right_children {
+                    // if right node exists, remove, merge and re-add, otherwise, just add it
+                    if let Some(left_node) = left_children.remove(&path) {
+                        let new_node = left_node.merge(right_node);
+                        left_children.insert(path, new_node);
+                    } else {
+                        left_children.insert(path, right_node);
+                    }
+                }
+            },
+            (_, _) => { /* conflict, but nothing to do, don't mutate left side */ },
+        }
+
+        left
+    }
+
+    /// Checks for conflicts in case the two trees would be merged.
+    ///
+    /// Follows the same rules as [`FsTree::try_merge`], nodes at the same path don't conflict if
+    /// both are directories (and their children don't conflict), both are regular files, or both
+    /// are symlinks with the same target. Regular files never conflict with each other because
+    /// the tree doesn't hold their contents, so there's nothing to lose.
+    ///
+    /// Also see [`Self::merge`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let left = tree! { file link -> a };
+    ///
+    /// assert!(!left.conflicts_with(&tree! { file link -> a }));
+    /// assert!(left.conflicts_with(&tree! { link -> b }));
+    /// assert!(left.conflicts_with(&tree! { file: {} }));
+    /// ```
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Directory(left_children), Self::Directory(right_children)) => {
+                right_children.iter().any(|(path, right_node)| {
+                    left_children
+                        .get(path)
+                        .is_some_and(|left_node| left_node.conflicts_with(right_node))
+                })
+            },
+            (Self::Regular, Self::Regular) => false,
+            (Self::Symlink(left_target), Self::Symlink(right_target)) => {
+                left_target != right_target
+            },
+            (_, _) => true,
+        }
+    }
+
+    /// Reference to children if `self.is_directory()`.
+    pub fn children(&self) -> Option<&TrieMap> {
+        match &self {
+            Self::Directory(children) => Some(children),
+            _ => None,
+        }
+    }
+
+    /// Mutable reference to children if `self.is_directory()`.
+    pub fn children_mut(&mut self) -> Option<&mut TrieMap> {
+        match self {
+            Self::Directory(children) => Some(children),
+            _ => None,
+        }
+    }
+
+    /// Reference to target path, if `self.is_symlink()`.
+    pub fn target(&self) -> Option<&Path> {
+        match &self {
+            Self::Symlink(target_path) => Some(target_path),
+            _ => None,
+        }
+    }
+
+    /// Mutable reference to target path, if `self.is_symlink()`.
+    pub fn target_mut(&mut self) -> Option<&mut PathBuf> {
+        match self {
+            Self::Symlink(target_path) => Some(target_path),
+            _ => None,
+        }
+    }
+
+    /// Apply a closure for each direct child of this FsTree.
+    ///
+    /// Only 1 level deep, does nothing if `self` isn't a directory.
+    pub fn apply_to_children(&mut self, f: impl FnMut(&mut Self)) {
+        if let Some(children) = self.children_mut() {
+            children.values_mut().for_each(f);
+        }
+    }
+
+    /// Apply a closure to `self` and all of its direct and indirect descendants.
+    ///
+    /// Nodes are visited in DFS order, each one before its children, so changes made to a node are
+    /// seen when visiting its children, e.g. if a directory is replaced, its old children aren't
+    /// visited.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let mut tree = tree! {
+    ///     dir: {
+    ///         link -> target
+    ///     }
+    ///     other_link -> target
+    /// };
+    ///
+    /// // Convert all symlinks into regular files
+    /// tree.apply_to_all(|node| {
+    ///     if node.is_symlink() {
+    ///         *node = FsTree::Regular;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(tree, tree! { dir: { link } other_link });
+    /// ```
+    pub fn apply_to_all(&mut self, mut f: impl FnMut(&mut Self)) {
+        self.__apply_to_all(&mut f);
+    }
+
+    fn __apply_to_all(&mut self, f: &mut impl FnMut(&mut Self)) {
+        f(self);
+
+        if let Some(children) = self.children_mut() {
+            for child in children.values_mut() {
+                child.__apply_to_all(f);
+            }
+        }
+    }
+
+    /// Recursively removes directories that contain no files (only empty directories, if any).
+    ///
+    /// `self` is never removed, even if it ends up empty.
+    pub fn prune_empty_dirs(&mut self) {
+        if let Some(children) = self.children_mut() {
+            children.retain(|_, child| {
+                child.prune_empty_dirs();
+                !(child.is_dir() && child.is_leaf())
+            });
+        }
+    }
+
+    /// Returns a copy of the tree without regular files, keeping only directories and symlinks.
+    ///
+    /// Useful for showing just the "interesting" structure, directories that end up empty are
+    /// kept, call [`FsTree::prune_empty_dirs`] on the result to remove them.
+    ///
+    /// The root is never removed, so if `self` is a regular file, it's returned as is.
+    pub fn without_regular_files(&self) -> Self {
+        match self {
+            Self::Directory(children) => {
+                let children = children
+                    .iter()
+                    .filter(|(_, child)| !child.is_regular())
+                    .map(|(name, child)| (name.clone(), child.without_regular_files()))
+                    .collect();
+
+                Self::Directory(children)
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// Recursively removes the nodes for which `pred` returns `false`.
+    ///
+    /// `pred` receives the path of each node, relative to the root, and the node itself. Nodes are
+    /// tested before their children, so removing a directory removes all of its descendants
+    /// without testing them.
+    ///
+    /// Directories that become empty after filtering are kept if `keep_empty_dirs` is `true`, and
+    /// removed otherwise, directories that were already empty are only tested by `pred`.
+    ///
+    /// `self` is never removed, even if it ends up empty.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let mut tree = tree! {
+    ///     src: {
+    ///         "lib.rs"
+    ///         "data.json"
+    ///     }
+    ///     assets: {
+    ///         "image.png"
+    ///     }
+    /// };
+    ///
+    /// // Keep only Rust files
+    /// tree.retain(false, |path, node| {
+    ///     node.is_dir() || path.extension().is_some_and(|extension| extension == "rs")
+    /// });
+    ///
+    /// assert_eq!(tree, tree! { src: { "lib.rs" } });
+    /// ```
+    pub fn retain(&mut self, keep_empty_dirs: bool, pred: impl Fn(&Path, &Self) -> bool) {
+        self.__retain(&mut PathBuf::new(), keep_empty_dirs, &pred);
+    }
+
+    fn __retain(
+        &mut self,
+        path: &mut PathBuf,
+        keep_empty_dirs: bool,
+        pred: &impl Fn(&Path, &Self) -> bool,
+    ) {
+        let Some(children) = self.children_mut() else {
+            return;
+        };
+
+        children.retain(|name, child| {
+            path.push(name);
+
+            let was_empty = child.is_leaf();
+            let keep = pred(path, child) && {
+                child.__retain(path, keep_empty_dirs, pred);
+                keep_empty_dirs || was_empty || !child.is_leaf()
+            };
+
+            path.pop();
+            keep
+        });
+    }
+
+    /// Splits the tree in two, the nodes for which `pred` returns `true`, and the remainder.
+    ///
+    /// `pred` receives the path of each node, relative to the root, and the node itself.
+    ///
+    /// The exact rule is:
+    /// - Leaves (regular files, symlinks and empty directories) are tested by `pred`, and moved
+    ///   into the matching half.
+    /// - Non-empty directories aren't tested, they're present in each half that got at least one
+    ///   of their descendants, so the directory structure is preserved in both halves, and
+    ///   directories whose descendants all went to one half are absent from the other.
+    /// - The root is always present in both halves, if `self` isn't a directory, it's tested with
+    ///   an empty path, and the other half is an empty directory.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///         link -> target
+    ///     }
+    ///     other_file
+    /// };
+    ///
+    /// let (symlinks, rest) = tree.partition(|_, node| node.is_symlink());
+    ///
+    /// assert_eq!(symlinks, tree! { dir: { link -> target } });
+    /// assert_eq!(rest, tree! { dir: { file } other_file });
+    /// ```
+    pub fn partition(self, pred: impl Fn(&Path, &Self) -> bool) -> (Self, Self) {
+        match self {
+            Self::Directory(children) => {
+                let (matching, remainder) = Self::__partition(children, &mut PathBuf::new(), &pred);
+                (Self::Directory(matching), Self::Directory(remainder))
+            },
+            leaf if pred(Path::new(""), &leaf) => (leaf, Self::new_dir()),
+            leaf => (Self::new_dir(), leaf),
+        }
+    }
+
+    fn __partition(
+        children: TrieMap,
+        path: &mut PathBuf,
+        pred: &impl Fn(&Path, &Self) -> bool,
+    ) -> (TrieMap, TrieMap) {
+        let mut matching = TrieMap::new();
+        let mut remainder = TrieMap::new();
+
+        for (name, child) in children {
+            path.push(&name);
+
+            match child {
+                Self::Directory(grandchildren) if !grandchildren.is_empty() => {
+                    let (left, right) = Self::__partition(grandchildren, path, pred);
+
+                    if !left.is_empty() {
+                        matching.insert(name.clone(), Self::Directory(left));
+                    }
+                    if !right.is_empty() {
+                        remainder.insert(name, Self::Directory(right));
+                    }
+                },
+                leaf if pred(path, &leaf) => {
+                    matching.insert(name, leaf);
+                },
+                leaf => {
+                    remainder.insert(name, leaf);
+                },
+            }
+
+            path.pop();
+        }
+
+        (matching, remainder)
+    }
+
+    /// Returns `true` if `self` is a leaf node.
+    ///
+    /// A leaf node might be of any type, including directory, however, a
+    /// non-leaf node is always a directory.
+    pub fn is_leaf(&self) -> bool {
+        match self {
+            Self::Regular | Self::Symlink(_) => true,
+            Self::Directory(children) => children.is_empty(),
+        }
+    }
+
+    /// The variant string, useful for showing to user.
+    pub fn variant_str(&self) -> &'static str {
+        match self {
+            Self::Regular => "regular file",
+            Self::Directory(_) => "directory",
+            Self::Symlink(_) => "symlink",
+        }
+    }
+
+    /// The [`FileType`] of this node.
+    pub fn file_type(&self) -> FileType {
+        match self {
+            Self::Regular => FileType::Regular,
+            Self::Directory(_) => FileType::Directory,
+            Self::Symlink(_) => FileType::Symlink,
+        }
+    }
+
+    /// Returns `true` if self matches the [`FsTree::Regular`] variant.
+    pub fn is_regular(&self) -> bool {
+        matches!(self, Self::Regular)
+    }
+
+    /// Returns `true` if self matches the [`FsTree::Directory`] variant.
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Self::Directory(_))
+    }
+
+    /// Returns `true` if self matches the [`FsTree::Symlink`] variant.
+    pub fn is_symlink(&self) -> bool {
+        matches!(self, Self::Symlink(_))
+    }
+
+    /// Write the tree structure in the path.
+    ///
+    /// Regular files are created empty, see [`FsTree::write_at_with`] to write their contents.
+    ///
+    /// The root node isn't written, `folder` plays its role, so only the children of the root are
+    /// created inside of it. To materialize the root too, write the tree at the parent of the
+    /// desired location, or use [`FsTree::write_at_mkdir`] to create `folder` first.
+    ///
+    /// See [`FsTree::write_at_with`] for how symlinks are created on Windows.
+    ///
+    /// # Errors:
+    ///
+    /// - If provided folder doesn't exist, or is not a directory.
+    /// - If any path already exists, see [`FsTree::write_at_with_options`] to overwrite them.
+    /// - [`Error::EscapingPathError`] if a node name could make a path escape `folder`, like `..`,
+    ///   nothing is written in this case. A `.` directory is fine, it's the same as its parent.
+    ///   Symlink targets are never checked, they can point anywhere.
+    /// - If any other IO error occurs.
+    pub fn write_at(&self, folder: impl AsRef<Path>) -> Result<()> {
+        self.write_at_with(folder, |_| None)
+    }
+
+    /// Write the tree structure in the path, overwriting existing paths as allowed by `options`.
+    ///
+    /// Regular files are created empty. With the default [`WriteOptions`], this is the same as
+    /// [`FsTree::write_at`].
+    ///
+    /// # Errors:
+    ///
+    /// - If provided folder doesn't exist, or is not a directory.
+    /// - If a path already exists, and `options` don't allow overwriting it.
+    /// - If any other IO error occurs, the error carries the offending path.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::{tree, WriteOptions};
+    ///
+    /// let dotfiles = tree! {
+    ///     ".bashrc" -> "dotfiles/.bashrc"
+    ///     ".vimrc" -> "dotfiles/.vimrc"
+    /// };
+    ///
+    /// // Replace old links, but don't touch real files
+    /// let options = WriteOptions::new().overwrite_symlinks(true);
+    /// dotfiles.write_at_with_options("/home/user", options).unwrap();
+    /// ```
+    pub fn write_at_with_options(
+        &self,
+        folder: impl AsRef<Path>,
+        options: WriteOptions,
+    ) -> Result<()> {
+        self.execute_write_plan(folder.as_ref(), options, |_| None)
+    }
+
+    /// Write the tree structure in the path, creating `folder` and its parents if missing.
+    ///
+    /// Like [`FsTree::write_at`], but `folder` doesn't need to exist, it's created with
+    /// [`fs::create_dir_all`], so an existing directory is fine too.
+    ///
+    /// # Errors:
+    ///
+    /// - If `folder` (or one of its parents) exists but is not a directory.
+    /// - If any other IO error occurs.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     src: {
+    ///         "main.rs"
+    ///     }
+    /// };
+    ///
+    /// tree.write_at_mkdir("new/project").unwrap();
+    /// ```
+    pub fn write_at_mkdir(&self, folder: impl AsRef<Path>) -> Result<()> {
+        let folder = folder.as_ref();
+        fs::create_dir_all(folder).map_err(|error| utils::io_error_at(error, folder))?;
+        self.write_at(folder)
+    }
+
+    /// Write the tree structure in the path, then restore the modes captured in `metadata`.
+    ///
+    /// Nodes without an entry in `metadata`, or without a [`NodeMetadata::mode`], keep the default
+    /// mode, so with an empty table, this is the same as [`FsTree::write_at`]. Symlinks and the
+    /// root (`folder`) are left untouched.
+    ///
+    /// Modes are applied after everything is written, children before their parents, so a
+    /// read-only directory doesn't prevent its children from being created.
+    ///
+    /// Only available on Unix.
+    ///
+    /// # Errors:
+    ///
+    /// - If provided folder doesn't exist, or is not a directory.
+    /// - If any other IO error occurs.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// // Clone a tree, keeping executable bits
+    /// let (tree, metadata) = FsTree::symlink_read_at_with_metadata("project").unwrap();
+    /// tree.write_at_preserving_mode("copy", &metadata).unwrap();
+    /// ```
+    ///
+    /// [`NodeMetadata::mode`]: crate::NodeMetadata::mode
+    #[cfg(unix)]
+    pub fn write_at_preserving_mode(
+        &self,
+        folder: impl AsRef<Path>,
+        metadata: &SideTable<NodeMetadata>,
+    ) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let folder = folder.as_ref();
+        self.write_at(folder)?;
+
+        for relative_path in self.paths_post_order() {
+            let is_symlink = self.get(&relative_path).is_some_and(Self::is_symlink);
+            if relative_path.as_os_str().is_empty() || is_symlink {
+                continue;
+            }
+
+            let Some(mode) = metadata
+                .get(&relative_path)
+                .and_then(|metadata| metadata.mode)
+            else {
+                continue;
+            };
+
+            let path = folder.join(&relative_path);
+            fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                .map_err(|error| utils::io_error_at(error, &path))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the actions [`FsTree::write_at`] would perform to write the tree in the path,
+    /// without touching the filesystem.
+    ///
+    /// Actions are in the order they would be executed, parents come before their children. As in
+    /// [`FsTree::write_at`], the root isn't included, `folder` plays its role, and neither are `.`
+    /// directories, which are their parents.
+    ///
+    /// Useful for implementing a dry-run mode.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, WriteAction};
+    ///
+    /// let tree = tree! {
+    ///     src: {
+    ///         "main.rs"
+    ///     }
+    ///     link -> src
+    /// };
+    ///
+    /// let plan = tree.write_plan("project");
+    ///
+    /// assert_eq!(plan, [
+    ///     WriteAction::CreateSymlink {
+    ///         path: "project/link".into(),
+    ///         target: "src".into(),
+    ///     },
+    ///     WriteAction::CreateDir("project/src".into()),
+    ///     WriteAction::CreateFile("project/src/main.rs".into()),
+    /// ]);
+    /// ```
+    pub fn write_plan(&self, folder: impl AsRef<Path>) -> Vec<WriteAction> {
+        self.__write_actions(folder.as_ref())
+            .map(|(action, _)| action)
+            .collect()
+    }
+
+    /// The [`FsTree::write_plan`] actions, each paired with its path relative to `folder`.
+    fn __write_actions<'a>(
+        &'a self,
+        folder: &'a Path,
+    ) -> impl Iterator<Item = (WriteAction, PathBuf)> + 'a {
+        self.iter()
+            .skip(1)
+            // A `.` directory is its parent, which is already created
+            .filter(|(node, relative_path)| {
+                !(node.is_dir() && utils::ends_with_cur_dir(relative_path))
+            })
+            .map(move |(node, relative_path)| {
+                let path = folder.join(&relative_path);
+
+                let action = match node {
+                    Self::Regular => WriteAction::CreateFile(path),
+                    Self::Directory(_) => WriteAction::CreateDir(path),
+                    Self::Symlink(target) => {
+                        WriteAction::CreateSymlink {
+                            path,
+                            target: target.clone(),
+                        }
+                    },
+                };
+
+                (action, relative_path)
+            })
+    }
+
+    /// Returns the paths that [`FsTree::write_at`] would create, but already exist in `folder`.
+    ///
+    /// Paths are checked with [`fs::symlink_metadata`], so an existing symlink is a conflict, even
+    /// if it's broken. The root isn't checked, since `folder` plays its role.
+    ///
+    /// Descendants of a path that's missing, or isn't a directory, can't exist, so they are never
+    /// reported, all paths returned are inside of `folder`, in the [`FsTree::write_plan`] order.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error other than [`io::ErrorKind::NotFound`] occurs.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     ".bashrc"
+    ///     ".config": {
+    ///         nvim
+    ///     }
+    /// };
+    ///
+    /// let conflicts = tree.write_conflicts("/home/user").unwrap();
+    ///
+    /// if conflicts.is_empty() {
+    ///     tree.write_at("/home/user").unwrap();
+    /// } else {
+    ///     eprintln!("Aborting, paths already exist: {conflicts:?}");
+    /// }
+    /// ```
+    pub fn write_conflicts(&self, folder: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let folder = folder.as_ref();
+        let mut conflicts = vec![];
+        let mut error = None;
+
+        self.walk(|relative_path, _| {
+            // The root and `.` directories aren't created, they're `folder` or their parent
+            if relative_path.as_os_str().is_empty() || utils::ends_with_cur_dir(relative_path) {
+                return WalkAction::Continue;
+            }
+
+            let path = folder.join(relative_path);
+
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) => {
+                    let is_dir = metadata.is_dir();
+                    conflicts.push(path);
+
+                    if is_dir {
+                        WalkAction::Continue
+                    } else {
+                        WalkAction::SkipSubtree
+                    }
+                },
+                Err(err) if err.kind() == io::ErrorKind::NotFound => WalkAction::SkipSubtree,
+                Err(err) => {
+                    error = Some(utils::io_error_at(err, &path));
+                    WalkAction::Stop
+                },
+            }
+        });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(conflicts),
+        }
+    }
+
+    /// Write the tree structure in the path, writing the contents of regular files given by
+    /// `contents`.
+    ///
+    /// `contents` is called with the path of each regular file (relative to `folder`), if it
+    /// returns `None`, the file is created empty. Directories and symlinks have no contents, so
+    /// `contents` isn't called for them.
+    ///
+    /// On Windows, file and directory symlinks are different, a directory symlink is created if
+    /// the target is a directory on disk, or in `self` (in case it's not written yet), otherwise,
+    /// a file symlink is created. Creating symlinks on Windows requires Developer Mode or the
+    /// `SeCreateSymbolicLinkPrivilege` privilege (usually, running as administrator).
+    ///
+    /// # Errors:
+    ///
+    /// - If provided folder doesn't exist, or is not a directory.
+    /// - If any other IO error occurs, the error carries the offending path.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use std::{collections::BTreeMap, path::PathBuf};
+    ///
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     "Cargo.toml"
+    ///     src: {
+    ///         "main.rs"
+    ///     }
+    /// };
+    ///
+    /// let contents = BTreeMap::from([(PathBuf::from("src/main.rs"), b"fn main() {}".to_vec())]);
+    ///
+    /// tree.write_at_with("project", |path| contents.get(path).cloned()).unwrap();
+    /// ```
+    pub fn write_at_with(
+        &self,
+        folder: impl AsRef<Path>,
+        contents: impl FnMut(&Path) -> Option<Vec<u8>>,
+    ) -> Result<()> {
+        self.execute_write_plan(folder.as_ref(), WriteOptions::default(), contents)
+    }
+
+    fn execute_write_plan(
+        &self,
+        folder: &Path,
+        options: WriteOptions,
+        mut contents: impl FnMut(&Path) -> Option<Vec<u8>>,
+    ) -> Result<()> {
+        // Checked before writing anything, so an escaping tree leaves `folder` untouched
+        if let Some(path) = self.__find_escaping_path(&mut PathBuf::new()) {
+            return Err(Error::EscapingPathError(folder.join(path)));
+        }
+
+        for (action, relative_path) in self.__write_actions(folder) {
+            let path = action.path();
+
+            let reused = if options.overwrites_anything() {
+                clear_write_destination(&action, options)
+                    .map_err(|error| utils::io_error_at(error, path))?
+            } else {
+                false
+            };
+
+            let result = match &action {
+                WriteAction::CreateFile(_) => {
+                    // `create_new` fails instead of truncating existing files, or writing
+                    // through existing symlinks
+                    fs::OpenOptions::new()
+                        .write(true)
+                        .create_new(true)
+                        .open(path)
+                        .and_then(|mut file| {
+                            let bytes = contents(&relative_path).unwrap_or_default();
+                            io::Write::write_all(&mut file, &bytes)
+                        })
+                },
+                WriteAction::CreateDir(_) if reused => Ok(()),
+                WriteAction::CreateDir(_) => fs::create_dir(path),
+                WriteAction::CreateSymlink { target, .. } => {
+                    utils::create_symlink(target, path, || {
+                        self.__symlink_target_is_dir(path, &relative_path, target)
+                    })
+                },
+            };
+
+            result.map_err(|error| utils::io_error_at(error, path))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the symlink at `link` (`relative_link` inside of `self`) points to a directory,
+    /// on disk, or in `self`, in case it's not written yet.
+    ///
+    /// Used on Windows, where file and directory symlinks are different.
+    fn __symlink_target_is_dir(&self, link: &Path, relative_link: &Path, target: &Path) -> bool {
+        let link_dir = relative_link.parent().unwrap_or(Path::new(""));
+        let in_tree = utils::normalize_lexically(&link_dir.join(target));
+
+        link.parent()
+            .is_some_and(|parent| parent.join(target).is_dir())
+            || self.get(in_tree).is_some_and(Self::is_dir)
+    }
+
+    /// Returns the path of the first node whose name isn't a single normal component.
+    ///
+    /// Writing such a node could escape the destination folder, with `..` or an absolute name, or
+    /// write through a symlink, like a `link/file` name next to a `link` symlink.
+    ///
+    /// A `.` directory is harmless, it's the same as its parent, but a `.` file or symlink would
+    /// replace the parent.
+    fn __find_escaping_path(&self, path: &mut PathBuf) -> Option<PathBuf> {
+        for (name, child) in self.children()? {
+            let mut components = name.components();
+            let is_normal = match (components.next(), components.next()) {
+                (Some(Component::Normal(_)), None) => true,
+                (Some(Component::CurDir), None) => child.is_dir(),
+                _ => false,
+            };
+
+            path.push(name);
+
+            if !is_normal {
+                return Some(path.clone());
+            }
+            if let Some(escaping) = child.__find_escaping_path(path) {
+                return Some(escaping);
+            }
+
+            path.pop();
+        }
+
+        None
+    }
+
+    /// Returns a reference to the node at the path, if any.
+    ///
+    /// # Errors:
+    ///
+    /// - Returns `None` if there is no node at the given path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::FsTree;
+    ///
+    /// let root = FsTree::from_path_text("a/b/c");
+    ///
+    /// // Indexing is relative from `root`, so `root` cannot be indexed.
+    /// assert_eq!(root, FsTree::from_path_text("a/b/c"));
+    /// assert_eq!(root["a"], FsTree::from_path_text("b/c"));
+    /// assert_eq!(root["a/b"], FsTree::from_path_text("c"));
+    /// assert_eq!(root["a"]["b"], FsTree::from_path_text("c"));
+    /// assert_eq!(root["a/b/c"], FsTree::Regular);
+    /// assert_eq!(root["a/b"]["c"], FsTree::Regular);
+    /// assert_eq!(root["a"]["b/c"], FsTree::Regular);
+    /// assert_eq!(root["a"]["b"]["c"], FsTree::Regular);
+    /// ```
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&Self> {
+        let path = path.as_ref();
+
+        // Split first piece from the rest
+        let (popped, path_rest) = {
+            let mut iter = path.iter();
+            let popped: Option<&Path> = iter.next().map(OsStr::as_ref);
+            (popped, iter.as_path())
+        };
+
+        // If path ended, we reached the desired node
+        let Some(popped) = popped else {
+            return Some(self);
+        };
+
+        // Corner case: if `.`, ignore it and call again with the rest
+        if popped == Path::new(".") {
+            return self.get(path_rest);
+        }
+
+        self.children()?
+            .get(popped)
+            .and_then(|child| child.get(path_rest))
+    }
+
+    /// Returns a mutable reference to the node at the path, if any.
+    ///
+    /// This is the mutable version of [`FsTree::get`].
+    pub fn get_mut(&mut self, path: impl AsRef<Path>) -> Option<&mut Self> {
+        let path = path.as_ref();
+
+        // Split first piece from the rest
+        let (popped, path_rest) = {
+            let mut iter = path.iter();
+            let popped: Option<&Path> = iter.next().map(OsStr::as_ref);
+            (popped, iter.as_path())
+        };
+
+        // If path ended, we reached the desired node
+        let Some(popped) = popped else {
+            return Some(self);
+        };
+
+        // Corner case: if `.`, ignore it and call again with the rest
+        if popped == Path::new(".") {
+            return self.get_mut(path_rest);
+        }
+
+        self.children_mut()?
+            .get_mut(popped)
+            .and_then(|child| child.get_mut(path_rest))
+    }
+
+    /// Returns `true` if there's a node at the path.
+    ///
+    /// Shorthand for `self.get(path).is_some()`, so an empty path always returns `true`.
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// Returns how many components deep the node at the path is, `0` for the root.
+    ///
+    /// `.` components are ignored, like in [`FsTree::get`], so this is the same value yielded by
+    /// [`Iter::with_depth`] for the node.
+    ///
+    /// Returns `None` if there's no node at the path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! { a: { b: { c } } };
+    ///
+    /// assert_eq!(tree.depth_of(""), Some(0));
+    /// assert_eq!(tree.depth_of("a/b/c"), Some(3));
+    /// assert_eq!(tree.depth_of("a/missing"), None);
+    /// ```
+    pub fn depth_of(&self, path: impl AsRef<Path>) -> Option<usize> {
+        let path = path.as_ref();
+        self.get(path)?;
+
+        let depth = path
+            .iter()
+            .filter(|&piece| piece != OsStr::new("."))
+            .count();
+
+        Some(depth)
+    }
+
+    /// Returns a reference to the subtree at the path, if any.
+    ///
+    /// Same as [`FsTree::get`], pairs with [`FsTree::subtree_len`].
+    pub fn subtree(&self, path: impl AsRef<Path>) -> Option<&Self> {
+        self.get(path)
+    }
+
+    /// Counts the nodes of the subtree at the path, including the node itself, like
+    /// [`FsTree::len_all`].
+    ///
+    /// Returns `None` if there's no node at the path.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     src: {
+    ///         bin: {
+    ///             "main.rs"
+    ///         }
+    ///         "lib.rs"
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(tree.subtree_len("src"), Some(4));
+    /// assert_eq!(tree.subtree_len("src/lib.rs"), Some(1));
+    /// assert_eq!(tree.subtree_len("missing"), None);
+    /// ```
+    pub fn subtree_len(&self, path: impl AsRef<Path>) -> Option<usize> {
+        self.get(path).map(Self::len_all)
+    }
+
+    /// Returns the path of the deepest directory that contains both `a` and `b`.
+    ///
+    /// A path contains itself, so if `a` is a directory and an ancestor of `b`, `a` is returned,
+    /// the root is represented by an empty path.
+    ///
+    /// Returns `None` if `a` or `b` aren't in the tree.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::path::PathBuf;
+    ///
+    /// use fs_tree::FsTree;
+    ///
+    /// let tree = FsTree::from_path_text("a/b/c").merge(FsTree::from_path_text("a/b/d/e"));
+    ///
+    /// assert_eq!(tree.common_ancestor("a/b/c", "a/b/d/e"), Some(PathBuf::from("a/b")));
+    /// assert_eq!(tree.common_ancestor("a/b/c", "a/b/c"), Some(PathBuf::from("a/b")));
+    /// assert_eq!(tree.common_ancestor("a/b/c", "missing"), None);
+    /// ```
+    pub fn common_ancestor(&self, a: impl AsRef<Path>, b: impl AsRef<Path>) -> Option<PathBuf> {
+        let (a, b) = (a.as_ref(), b.as_ref());
+
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+
+        let is_normal = |component: &Component| *component != Component::CurDir;
+        let mut common: PathBuf = a
+            .components()
+            .filter(is_normal)
+            .zip(b.components().filter(is_normal))
+            .take_while(|(a, b)| a == b)
+            .map(|(component, _)| component)
+            .collect();
+
+        // Both paths exist, so only the last component might not be a directory
+        if !self.get(&common).is_some_and(Self::is_dir) {
+            common.pop();
+        }
+
+        self.is_dir().then_some(common)
+    }
+
+    /// Inserts a node at the given path.
+    ///
+    /// An empty path replaces `self`. See [`FsTree::try_insert`] for the non-panicking version.
+    ///
+    /// # Panics:
+    ///
+    /// - If there are no directories up to the path node in order to insert it.
+    pub fn insert(&mut self, path: impl AsRef<Path>, node: Self) {
+        if let Err(err) = self.try_insert(path, node) {
+            panic!("Failed to insert node, {err}");
+        }
+    }
+
+    /// Inserts a node at the given path, returning the node it replaced, if any.
+    ///
+    /// An empty path replaces `self`.
+    ///
+    /// # Errors:
+    ///
+    /// - If a parent directory doesn't exist, or isn't a directory, the error carries the path
+    ///   of that parent.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree, InsertError};
+    ///
+    /// let mut tree = tree! {
+    ///     file
+    /// };
+    ///
+    /// assert_eq!(tree.try_insert("file", FsTree::new_dir()), Ok(Some(FsTree::Regular)));
+    /// assert_eq!(tree.try_insert("file/inner", FsTree::Regular), Ok(None));
+    ///
+    /// assert_eq!(
+    ///     tree.try_insert("missing/file", FsTree::Regular),
+    ///     Err(InsertError::MissingParent("missing".into())),
+    /// );
+    /// assert_eq!(
+    ///     tree.try_insert("file/inner/file", FsTree::Regular),
+    ///     Err(InsertError::NotADirectory {
+    ///         path: "file/inner".into(),
+    ///         variant: "regular file",
+    ///     }),
+    /// );
+    /// ```
+    pub fn try_insert(
+        &mut self,
+        path: impl AsRef<Path>,
+        node: Self,
+    ) -> std::result::Result<Option<Self>, InsertError> {
+        let mut iter = path.as_ref().iter();
+
+        let Some(node_name) = iter.next_back().map(Path::new) else {
+            return Ok(Some(mem::replace(self, node)));
+        };
+
+        let mut tree = self;
+        let mut parent_path = PathBuf::new();
+
+        // Traverse tree
+        for next in iter {
+            // Corner case: `.` is the current node, like in `FsTree::get`
+            if next == "." {
+                continue;
+            }
+
+            let Self::Directory(children) = tree else {
+                return Err(InsertError::NotADirectory {
+                    path: parent_path,
+                    variant: tree.variant_str(),
+                });
+            };
+
+            parent_path.push(next);
+
+            tree = match children.get_mut(Path::new(next)) {
+                Some(child) => child,
+                None => return Err(InsertError::MissingParent(parent_path)),
+            };
+        }
+
+        match tree {
+            Self::Directory(children) => Ok(children.insert(node_name.into(), node)),
+            _ => {
+                Err(InsertError::NotADirectory {
+                    path: parent_path,
+                    variant: tree.variant_str(),
+                })
+            },
+        }
+    }
+
+    /// Returns the directory at the given path, creating it and any missing parent directories.
+    ///
+    /// An empty path returns `self`.
+    ///
+    /// # Panics:
+    ///
+    /// - If `self` or any node along the path exists, but isn't a directory.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let mut tree = FsTree::new_dir();
+    ///
+    /// let dir = tree.get_or_insert_dir("a/b/c");
+    /// dir.insert("file", FsTree::Regular);
+    ///
+    /// assert_eq!(tree, tree! { a: { b: { c: { file } } } });
+    /// ```
+    pub fn get_or_insert_dir(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        let mut tree = self;
+        let mut current_path = PathBuf::new();
+
+        for next in path.as_ref().iter() {
+            if next == "." {
+                continue;
+            }
+
+            let Self::Directory(children) = tree else {
+                panic!(
+                    "Failed to get or insert directory, {}",
+                    InsertError::NotADirectory {
+                        path: current_path,
+                        variant: tree.variant_str(),
+                    }
+                );
+            };
+
+            current_path.push(next);
+
+            tree = children
+                .entry(PathBuf::from(next))
+                .or_insert_with(Self::new_dir);
+        }
+
+        if !tree.is_dir() {
+            panic!(
+                "Failed to get or insert directory, {}",
+                InsertError::NotADirectory {
+                    path: current_path,
+                    variant: tree.variant_str(),
+                }
+            );
+        }
+
+        tree
+    }
+
+    /// Removes the node at the given path, returning it.
+    ///
+    /// Returns `None` if there's no node at the path, including when one of the parents isn't a
+    /// directory. The root can't be removed, so an empty path also returns `None`.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let mut tree = tree! {
+    ///     a: {
+    ///         b
+    ///     }
+    ///     c
+    /// };
+    ///
+    /// assert_eq!(tree.remove("a/b"), Some(FsTree::Regular));
+    /// assert_eq!(tree.remove("a/b"), None);
+    /// assert_eq!(tree, tree! { a: {} c });
+    /// ```
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
+        let (parent, name) = (path.parent()?, path.file_name()?);
+
+        self.get_mut(parent)?
+            .children_mut()?
+            .remove(Path::new(name))
+    }
+}
+
+#[cfg(feature = "libc-file-type")]
+impl FsTree {
+    /// Returns the file type equivalent [`libc::mode_t`] value.
+    pub fn as_mode_t(&self) -> libc::mode_t {
+        match self {
+            Self::Regular => libc::S_IFREG,
+            Self::Directory(_) => libc::S_IFDIR,
+            Self::Symlink(_) => libc::S_IFCHR,
+        }
+    }
+}
+
+impl<P> FromIterator<P> for FsTree
+where
+    P: AsRef<Path>,
+{
+    /// See [`FsTree::from_paths`].
+    fn from_iter<I: IntoIterator<Item = P>>(paths: I) -> Self {
+        Self::from_paths(paths)
+    }
+}
+
+impl<'a> IntoIterator for &'a FsTree {
+    type Item = (&'a FsTree, PathBuf);
+    type IntoIter = Iter<'a>;
+
+    /// See [`FsTree::iter`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    /// };
+    ///
+    /// for (node, path) in &tree {
+    ///     println!("{}: {}", path.display(), node.variant_str());
+    /// }
+    /// ```
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<P> Index<P> for FsTree
+where
+    P: AsRef<Path>,
+{
+    type Output = FsTree;
+
+    fn index(&self, path: P) -> &Self::Output {
+        self.get(path.as_ref())
+            .unwrap_or_else(|| panic!("no node found for path '{}'", path.as_ref().display()))
+    }
+}
+
+/// Removes what's in the way of `action`, as allowed by `options`.
+///
+/// Returns `true` if an existing directory is reused for a [`WriteAction::CreateDir`], if the
+/// path is left occupied otherwise, creating it fails.
+fn clear_write_destination(action: &WriteAction, options: WriteOptions) -> io::Result<bool> {
+    let path = action.path();
+
+    let file_type = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata.file_type(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error),
+    };
+
+    if file_type.is_symlink() {
+        if options.overwrite_symlinks {
+            utils::remove_symlink(path)?;
+        }
+    } else if file_type.is_dir() {
+        if options.overwrite_dirs {
+            if matches!(action, WriteAction::CreateDir(_)) {
+                return Ok(true);
+            }
+            fs::remove_dir_all(path)?;
+        }
+    } else if options.overwrite_files {
+        fs::remove_file(path)?;
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use super::*;
+    use crate::{tree, utils::testdir};
+
+    #[test]
+    fn test_len_leafs_and_len_all() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                    d -> target
+                }
+                e
+            }
+            f
+        };
+
+        assert_eq!(tree.len_leafs(), 4);
+        assert_eq!(tree.len_all(), 7);
+        assert_eq!(tree.len_all(), tree.nodes().count());
+        assert_eq!(tree["a/b"].len_all(), 3);
+
+        assert_eq!(FsTree::Regular.len_leafs(), 1);
+        assert_eq!(FsTree::Regular.len_all(), 1);
+        assert_eq!(FsTree::new_dir().len_all(), 1);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(FsTree::new_dir().is_empty());
+        assert!(!tree! { a }.is_empty());
+        assert!(!FsTree::Regular.is_empty());
+        assert!(!FsTree::Symlink("target".into()).is_empty());
+    }
+
+    #[test]
+    fn test_insert_basic() {
+        let mut tree = FsTree::new_dir();
+
+        let paths = ["a", "a/b", "a/b/c", "a/b/c/d", "a/b/c/d/e"];
+        for path in paths {
+            tree.insert(path, FsTree::new_dir());
+        }
+
+        tree.insert("a/b/c/d/e/f", FsTree::Regular);
+
+        let expected = tree! {
+            a: { b: { c: { d: { e: { f } } } } }
+        };
+
+        assert_eq!(tree, expected);
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut tree = tree! {
+            dir: {}
+            file
+            link -> target
+        };
+
+        assert_eq!(tree.try_insert("dir/a", FsTree::Regular), Ok(None));
+        assert_eq!(
+            tree.try_insert("dir/a", FsTree::new_dir()),
+            Ok(Some(FsTree::Regular))
+        );
+        assert_eq!(tree.try_insert("dir/a/b", FsTree::Regular), Ok(None));
+
+        assert_eq!(
+            tree.try_insert("dir/missing/b", FsTree::Regular),
+            Err(InsertError::MissingParent("dir/missing".into()))
+        );
+        assert_eq!(
+            tree.try_insert("file/b", FsTree::Regular),
+            Err(InsertError::NotADirectory {
+                path: "file".into(),
+                variant: "regular file",
+            })
+        );
+        assert_eq!(
+            tree.try_insert("link/b/c", FsTree::Regular),
+            Err(InsertError::NotADirectory {
+                path: "link".into(),
+                variant: "symlink",
+            })
+        );
+
+        let expected = tree! {
+            dir: {
+                a: {
+                    b
+                }
+            }
+            file
+            link -> target
+        };
+        assert_eq!(tree, expected);
+
+        // `.` parents are skipped
+        assert_eq!(tree.try_insert("./dir/./c", FsTree::Regular), Ok(None));
+        assert_eq!(
+            tree.try_insert("./missing/c", FsTree::Regular),
+            Err(InsertError::MissingParent("missing".into()))
+        );
+        tree.insert("./dir/a/d", FsTree::Regular);
+        assert!(tree.contains("dir/c"));
+        assert!(tree.contains("dir/a/d"));
+
+        // Empty path replaces the root, which can be a regular file
+        let mut tree = FsTree::Regular;
+        assert_eq!(
+            tree.try_insert("", FsTree::new_dir()),
+            Ok(Some(FsTree::Regular))
+        );
+        assert_eq!(
+            FsTree::Regular.try_insert("a", FsTree::Regular),
+            Err(InsertError::NotADirectory {
+                path: "".into(),
+                variant: "regular file",
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic = "parent directory missing doesn't exist"]
+    fn test_insert_panics_on_missing_parent() {
+        FsTree::new_dir().insert("missing/file", FsTree::Regular);
+    }
+
+    #[test]
+    fn test_get_or_insert_dir() {
+        let mut tree = tree! {
+            a: {
+                file
+            }
+        };
+
+        tree.get_or_insert_dir("a/b/c").insert("d", FsTree::Regular);
+        tree.get_or_insert_dir("./a/b").insert("e", FsTree::Regular);
+        let copy = tree.clone();
+        assert_eq!(tree.get_or_insert_dir(""), &copy);
+
+        let expected = tree! {
+            a: {
+                file
+                b: {
+                    c: {
+                        d
+                    }
+                    e
+                }
+            }
+        };
+
+        assert_eq!(tree, expected);
+    }
+
+    #[test]
+    #[should_panic = "parent a/file is not a directory, but a regular file"]
+    fn test_get_or_insert_dir_panics_on_file() {
+        tree! { a: { file } }.get_or_insert_dir("a/file/b");
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut tree = tree! {
+            a: {
+                b: {
+                    c
+                }
+                link -> target
+            }
+            file
+        };
+
+        assert_eq!(tree.remove("a/b"), Some(tree! { c }));
+        assert_eq!(
+            tree.remove("./a/link"),
+            Some(FsTree::Symlink("target".into()))
+        );
+
+        // Missing nodes, non-directory parents and the root
+        assert_eq!(tree.remove("a/b"), None);
+        assert_eq!(tree.remove("file/child"), None);
+        assert_eq!(tree.remove(""), None);
+        assert_eq!(tree.remove("."), None);
+
+        assert_eq!(tree, tree! { a: {} file });
+    }
+
+    #[rustfmt::skip]
+    #[test]
+    fn test_insert_complete() {
+        let result = {
+            let mut tree = FsTree::new_dir();
+            tree.insert("config1", FsTree::Regular);
+            tree.insert("config2", FsTree::Regular);
+            tree.insert("outer_dir", FsTree::new_dir());
+            tree.insert("outer_dir/file1", FsTree::Regular);
+            tree.insert("outer_dir/file2", FsTree::Regular);
+            tree.insert("outer_dir/inner_dir", FsTree::new_dir());
+            tree.insert("outer_dir/inner_dir/inner1", FsTree::Regular);
+            tree.insert("outer_dir/inner_dir/inner2", FsTree::Regular);
+            tree.insert("outer_dir/inner_dir/inner3", FsTree::Regular);
+            tree.insert("outer_dir/inner_dir/inner_link", FsTree::Symlink("inner_target".into()));
+            tree.insert("link", FsTree::Symlink("target".into()));
+            tree.insert("config3", FsTree::Regular);
+            tree
+        };
+
+        let expected = tree! {
+            config1
+            config2
+            outer_dir: {
+                file1
+                file2
+                inner_dir: {
+                    inner1
+                    inner2
+                    inner3
+                    inner_link -> inner_target
+                }
+            }
+            link -> target
+            config3
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_write_at() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                    empty: {}
+                    link -> target
+                }
+            }
+        };
+
+        tree.write_at(test_dir).unwrap();
+
+        let result = FsTree::symlink_read_at(test_dir).unwrap();
+
+        assert_eq!(result, tree);
+    }
+
+    #[test]
+    fn test_depth_of_and_subtree_len() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                    d -> target
+                }
+                empty: {}
+            }
+            e
+        };
+
+        assert_eq!(tree.depth_of(""), Some(0));
+        assert_eq!(tree.depth_of("a"), Some(1));
+        assert_eq!(tree.depth_of("./a/./b"), Some(2));
+        assert_eq!(tree.depth_of("a/b/d"), Some(3));
+        assert_eq!(tree.depth_of("a/b/d/x"), None);
+        assert_eq!(tree.depth_of("missing"), None);
+
+        // Matches the depths reported while iterating
+        for (depth, _, path) in tree.iter().with_depth() {
+            assert_eq!(tree.depth_of(&path), Some(depth));
+        }
+
+        assert_eq!(tree.subtree("a/b"), tree.get("a/b"));
+        assert_eq!(tree.subtree_len(""), Some(tree.len_all()));
+        assert_eq!(tree.subtree_len("a"), Some(5));
+        assert_eq!(tree.subtree_len("a/empty"), Some(1));
+        assert_eq!(tree.subtree_len("e"), Some(1));
+        assert_eq!(tree.subtree_len("missing"), None);
+    }
+
+    #[test]
+    fn test_into_iter_ref() {
+        let tree = tree! {
+            dir: {
+                file
+            }
+            link -> dir
+        };
+
+        let mut visited = vec![];
+        for (node, path) in &tree {
+            visited.push((node, path));
+        }
+
+        assert_eq!(visited, tree.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let tree = tree! {
+            a -> target_a
+            dir: {
+                file
+                b -> "../target_b"
+                nested: {
+                    c -> "/absolute"
+                }
+            }
+            d -> dir
+        };
+
+        let symlinks: Vec<(PathBuf, &Path)> = tree.symlinks().collect();
+        let expected = [
+            ("a", "target_a"),
+            ("d", "dir"),
+            ("dir/b", "../target_b"),
+            ("dir/nested/c", "/absolute"),
+        ];
+        assert_eq!(
+            symlinks,
+            expected.map(|(path, target)| (PathBuf::from(path), Path::new(target)))
+        );
+
+        assert_eq!(tree! { file }.symlinks().count(), 0);
+        let root = FsTree::Symlink("target".into());
+        assert_eq!(
+            root.symlinks().collect::<Vec<_>>(),
+            [(PathBuf::new(), Path::new("target"))]
+        );
+    }
+
+    #[test]
+    fn test_write_plan() {
+        // The example from the `tree!` docs
+        let tree = tree! {
+            file1
+            outer_dir: {
+                file2
+                inner_dir: {
+                    file3
+                }
+                link1 -> target
+                link2 -> "/home/username/.gitconfig"
+            }
+        };
+
+        let expected = [
+            WriteAction::CreateFile("dest/file1".into()),
+            WriteAction::CreateDir("dest/outer_dir".into()),
+            WriteAction::CreateFile("dest/outer_dir/file2".into()),
+            WriteAction::CreateDir("dest/outer_dir/inner_dir".into()),
+            WriteAction::CreateFile("dest/outer_dir/inner_dir/file3".into()),
+            WriteAction::CreateSymlink {
+                path: "dest/outer_dir/link1".into(),
+                target: "target".into(),
+            },
+            WriteAction::CreateSymlink {
+                path: "dest/outer_dir/link2".into(),
+                target: "/home/username/.gitconfig".into(),
+            },
+        ];
+
+        assert_eq!(tree.write_plan("dest"), expected);
+        assert_eq!(FsTree::Regular.write_plan("dest"), []);
+
+        // `.` directories are skipped, but not their children
+        let with_cur_dir = tree! { ".": { file } };
+        assert_eq!(
+            with_cur_dir.write_plan("dest"),
+            [WriteAction::CreateFile("dest/./file".into())]
+        );
+
+        // The plan is what gets executed
+        let (_dropper, test_dir) = testdir().unwrap();
+        tree.write_at(test_dir).unwrap();
+
+        for action in tree.write_plan(test_dir) {
+            let file_type = FileType::symlink_read_at(action.path()).unwrap();
+            let expected = match action {
+                WriteAction::CreateFile(_) => FileType::Regular,
+                WriteAction::CreateDir(_) => FileType::Directory,
+                WriteAction::CreateSymlink { .. } => FileType::Symlink,
+            };
+            assert_eq!(file_type, expected);
+        }
+    }
+
+    #[test]
+    fn test_write_conflicts() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+                nested: {
+                    inner
+                }
+            }
+            link -> missing_target
+        };
+
+        assert_eq!(tree.write_conflicts(test_dir).unwrap(), [] as [PathBuf; 0]);
+
+        tree.write_at(test_dir).unwrap();
+
+        let expected: Vec<PathBuf> = ["dir", "dir/file", "dir/nested", "dir/nested/inner", "link"]
+            .iter()
+            .map(|path| test_dir.join(path))
+            .collect();
+        assert_eq!(tree.write_conflicts(test_dir).unwrap(), expected);
+
+        // Paths below a file are skipped, and missing ones are ignored
+        fs::remove_dir_all(test_dir.join("dir/nested")).unwrap();
+        fs::write(test_dir.join("dir/nested"), "").unwrap();
+        fs::remove_file(test_dir.join("dir/file")).unwrap();
+
+        let expected: Vec<PathBuf> = ["dir", "dir/nested", "link"]
+            .iter()
+            .map(|path| test_dir.join(path))
+            .collect();
+        assert_eq!(tree.write_conflicts(test_dir).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_write_at_escaping_paths() {
+        let (_dropper, test_dir) = testdir().unwrap();
+        let folder = test_dir.join("folder");
+        fs::create_dir(&folder).unwrap();
+
+        let absolute = test_dir.join("escaped");
+        let escaping = [
+            (FsTree::from_path_text("../escaped"), Path::new("..")),
+            (
+                FsTree::from_path_text("dir/../../escaped"),
+                Path::new("dir/.."),
+            ),
+            (
+                FsTree::Directory(TrieMap::from([(absolute.clone(), FsTree::Regular)])),
+                &absolute,
+            ),
+            // Would write through `link`
+            (
+                tree! { link -> ".." "link/escaped" },
+                Path::new("link/escaped"),
+            ),
+        ];
+
+        for (tree, path) in escaping {
+            let error = tree.write_at(&folder).unwrap_err();
+            assert!(matches!(error, Error::EscapingPathError(_)));
+            assert_eq!(error.path(), Some(&folder.join(path)));
+        }
+
+        // Nothing was written
+        assert_eq!(fs::read_dir(&folder).unwrap().count(), 0);
+        assert_eq!(fs::read_dir(test_dir).unwrap().count(), 1);
+
+        // `.` directories are their parent, but a `.` file would replace it
+        let error = tree! { "." }.write_at(&folder).unwrap_err();
+        assert!(matches!(error, Error::EscapingPathError(_)));
+
+        let tree = FsTree::from_path_text("./dir/./file");
+        assert!(tree.write_conflicts(&folder).unwrap().is_empty());
+        tree.write_at(&folder).unwrap();
+        assert_eq!(FsTree::read_at(&folder).unwrap(), tree! { dir: { file } });
+
+        // But symlink targets can point outside
+        tree! { link -> "../outside" }.write_at(&folder).unwrap();
+    }
+
+    #[test]
+    fn test_symlink_target_is_dir() {
+        let (_dropper, test_dir) = testdir().unwrap();
+        // Parents are written before the links inside of them
+        tree! { on_disk: {} file dir: {} }
+            .write_at(test_dir)
+            .unwrap();
+
+        let tree = tree! {
+            dir: {
+                in_tree: {}
+                "file.txt"
+            }
+        };
+        let is_dir = |relative_link: &str, target: &str| {
+            let link = test_dir.join(relative_link);
+            tree.__symlink_target_is_dir(&link, Path::new(relative_link), Path::new(target))
+        };
+
+        // Directories, on disk or only in the tree
+        assert!(is_dir("link", "on_disk"));
+        assert!(is_dir("dir/link", "../on_disk"));
+        assert!(is_dir("dir/link", "in_tree"));
+        assert!(is_dir("link", "./dir/in_tree"));
+        assert!(is_dir("link", &test_dir.join("on_disk").to_string_lossy()));
+
+        // Files, and missing targets, fall back to a file symlink
+        assert!(!is_dir("link", "file"));
+        assert!(!is_dir("dir/link", "file.txt"));
+        assert!(!is_dir("link", "missing"));
+    }
+
+    #[test]
+    fn test_write_at_mkdir() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+            }
+        };
+
+        let folder = test_dir.join("new/nested");
+        assert!(tree.write_at(&folder).is_err());
+
+        tree.write_at_mkdir(&folder).unwrap();
+        assert_eq!(FsTree::symlink_read_at(&folder).unwrap(), tree);
+
+        // Existing, but not a directory
+        let result = tree.write_at_mkdir(folder.join("dir/file"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_read_structure_at() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            dir: {
+                file
+                extra
+            }
+            was_file: {
+                inner
+            }
+            was_dir
+            link -> dir
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let structure = tree! {
+            dir: {
+                file
+                missing
+            }
+            was_file
+            was_dir: {
+                inner
+            }
+            link
+        };
+
+        let expected = tree! {
+            dir: {
+                file
+            }
+            was_file: {}
+            was_dir
+            link -> dir
+        };
+        assert_eq!(
+            structure.symlink_read_structure_at(test_dir).unwrap(),
+            expected
+        );
+
+        let expected = tree! {
+            dir: {
+                file
+            }
+            was_file: {}
+            was_dir
+            link: {}
+        };
+        assert_eq!(structure.read_structure_at(test_dir).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_read_at_specific_errors() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! { file }.write_at(test_dir).unwrap();
+
+        let missing = test_dir.join("missing");
+        assert!(matches!(
+            FsTree::read_at(&missing),
+            Err(Error::NotFoundError(path)) if path == missing
+        ));
+        assert!(matches!(
+            FsTree::symlink_read_at(&missing),
+            Err(Error::NotFoundError(path)) if path == missing
+        ));
+        assert!(matches!(
+            tree! { a }.read_structure_at(&missing),
+            Err(Error::NotFoundError(path)) if path == missing
+        ));
+
+        let inside_file = test_dir.join("file/inner");
+        assert!(matches!(
+            FsTree::read_at(&inside_file),
+            Err(Error::NotADirectoryError(path)) if path == inside_file
+        ));
+        assert!(matches!(
+            tree! { a }.read_structure_at(&inside_file),
+            Err(Error::NotADirectoryError(path)) if path == inside_file
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_structure_at_unexpected_root() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let socket = test_dir.join("socket");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+        assert!(matches!(
+            tree! { a }.read_structure_at(&socket),
+            Err(Error::UnexpectedFileTypeError(FileType::Socket, path)) if path == socket
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_at_preserving_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode_at = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+
+        let (_dropper, source) = testdir().unwrap();
+        let tree = tree! {
+            bin: {
+                script
+            }
+            readonly: {
+                file
+            }
+            link -> "bin/script"
+        };
+        tree.write_at(source).unwrap();
+        let set_mode = |path: &str, mode| {
+            let permissions = std::fs::Permissions::from_mode(mode);
+            fs::set_permissions(source.join(path), permissions).unwrap();
+        };
+        set_mode("bin/script", 0o755);
+        set_mode("readonly/file", 0o444);
+        set_mode("readonly", 0o555);
+
+        let (result, metadata) = FsTree::symlink_read_at_with_metadata(source).unwrap();
+
+        let (_dropper, copy) = testdir().unwrap();
+        result.write_at_preserving_mode(copy, &metadata).unwrap();
+
+        assert_eq!(FsTree::symlink_read_at(copy).unwrap(), tree);
+        assert_eq!(mode_at(&copy.join("bin/script")), 0o755);
+        assert_eq!(mode_at(&copy.join("readonly/file")), 0o444);
+        assert_eq!(mode_at(&copy.join("readonly")), 0o555);
+
+        // Without metadata, default modes are kept
+        let (_dropper, plain) = testdir().unwrap();
+        result
+            .write_at_preserving_mode(plain, &SideTable::new())
+            .unwrap();
+        assert_eq!(mode_at(&plain.join("bin/script")) & 0o111, 0);
+
+        // Let the temporary directories be removed
+        for dir in [source, copy] {
+            let permissions = std::fs::Permissions::from_mode(0o755);
+            fs::set_permissions(dir.join("readonly"), permissions).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_write_at_with() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                with_contents
+                empty
+            }
+            link -> "dir/with_contents"
+        };
+
+        let mut called_with = vec![];
+        tree.write_at_with(test_dir, |path| {
+            called_with.push(path.to_path_buf());
+            (path == Path::new("dir/with_contents")).then(|| b"contents".to_vec())
+        })
+        .unwrap();
+
+        assert_eq!(
+            called_with,
+            ["dir/empty", "dir/with_contents"].map(PathBuf::from)
+        );
+        assert_eq!(FsTree::symlink_read_at(test_dir).unwrap(), tree);
+        assert_eq!(
+            fs::read(test_dir.join("dir/with_contents")).unwrap(),
+            b"contents"
+        );
+        assert_eq!(fs::read(test_dir.join("dir/empty")).unwrap(), b"");
+
+        // Fails because it already exists
+        let error = tree.write_at_with(test_dir, |_| None).unwrap_err();
+        assert_eq!(error.path(), Some(&test_dir.join("dir")));
+    }
+
+    #[test]
+    fn test_write_at_with_options() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+            }
+            file
+            link -> target
+        };
+
+        let occupied = tree! {
+            dir: {
+                extra
+            }
+            file -> elsewhere
+            link: {
+                inner
+            }
+        };
+
+        // Default options, nothing is overwritten
+        occupied.write_at(test_dir).unwrap();
+        let error = tree
+            .write_at_with_options(test_dir, WriteOptions::new())
+            .unwrap_err();
+        assert_eq!(error.path(), Some(&test_dir.join("dir")));
+
+        // Each toggle only affects its own file type
+        let options = WriteOptions::new().overwrite_files(true);
+        let error = tree.write_at_with_options(test_dir, options).unwrap_err();
+        assert_eq!(error.path(), Some(&test_dir.join("dir")));
+
+        let options = WriteOptions::new().overwrite_dirs(true);
+        let error = tree.write_at_with_options(test_dir, options).unwrap_err();
+        assert_eq!(error.path(), Some(&test_dir.join("file")));
+
+        // The failed attempt above already wrote `dir/file`
+        let options = options.overwrite_files(true).overwrite_symlinks(true);
+        tree.write_at_with_options(test_dir, options).unwrap();
+
+        // `dir` is reused, `link` replaced a directory, `file` replaced a symlink
+        let expected = tree! {
+            dir: {
+                extra
+                file
+            }
+            file
+            link -> target
+        };
+        assert_eq!(FsTree::symlink_read_at(test_dir).unwrap(), expected);
+
+        // Regular files in the way
+        let tree = tree! { extra };
+        let error = tree.write_at(test_dir.join("dir")).unwrap_err();
+        assert_eq!(error.path(), Some(&test_dir.join("dir/extra")));
+
+        let options = WriteOptions::new().overwrite_files(true);
+        tree.write_at_with_options(test_dir.join("dir"), options)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_try_read_at() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+            }
+        };
+        tree.write_at(test_dir).unwrap();
+
+        assert_eq!(FsTree::try_read_at(test_dir).unwrap(), Some(tree));
+        assert_eq!(FsTree::try_read_at(test_dir.join("missing")).unwrap(), None);
+        assert!(FsTree::try_read_at(test_dir.join("dir/file/inner")).is_err());
+    }
+
+    #[test]
+    fn test_follow_symlink_chain() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+                link_to_file -> file
+            }
+            link_to_link -> "dir/link_to_file"
+            loop_a -> loop_b
+            loop_b -> loop_a
+            broken -> missing
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let follow =
+            |path: &str, max_hops| FsTree::follow_symlink_chain(test_dir.join(path), max_hops);
+
+        assert_eq!(follow("dir/file", 0).unwrap(), test_dir.join("dir/file"));
+        assert_eq!(
+            follow("link_to_link", 2).unwrap(),
+            test_dir.join("dir/file")
+        );
+        assert!(follow("link_to_link", 1).is_err());
+        assert!(follow("loop_a", 100).is_err());
+        assert_eq!(
+            follow("broken", 1).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_resolve_symlink() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+                single -> file
+                up -> "../top"
+            }
+            top -> "dir/single"
+            self_loop -> self_loop
+            loop_a -> loop_b
+            loop_b -> loop_a
+            broken -> missing
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let resolve = |path: &str| FsTree::resolve_symlink(test_dir.join(path));
+
+        // Not a symlink
+        assert_eq!(resolve("dir").unwrap(), test_dir.join("dir"));
+
+        // Single hop
+        assert_eq!(resolve("dir/single").unwrap(), test_dir.join("dir/file"));
+
+        // Multiple hops, relative to each link's parent
+        let resolved = resolve("dir/up").unwrap();
+        assert_eq!(
+            fs::canonicalize(resolved).unwrap(),
+            fs::canonicalize(test_dir.join("dir/file")).unwrap()
+        );
+
+        assert!(resolve("self_loop").is_err());
+        assert!(resolve("loop_a").is_err());
+        assert_eq!(
+            resolve("broken").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_read_at_symlink_loops() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! { a -> b  b -> a }.write_at(test_dir).unwrap();
+        let error = FsTree::read_at(test_dir.join("a")).unwrap_err();
+        assert!(matches!(error, Error::SymlinkLoopError(path) if path == test_dir.join("a")));
+
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! { dir: { up -> ".." } }.write_at(test_dir).unwrap();
+        let error = FsTree::read_at(test_dir).unwrap_err();
+        assert!(matches!(error, Error::SymlinkLoopError(path) if path == test_dir.join("dir/up")));
+
+        // Without following, loops are just symlinks
+        let expected = tree! { dir: { up -> ".." } };
+        assert_eq!(FsTree::symlink_read_at(test_dir).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_resolve_symlink_targets() {
+        let (_dropper, test_dir) = testdir().unwrap();
+        let test_dir = fs::canonicalize(test_dir).unwrap();
+        let file = test_dir.join("dir/file");
+
+        let mut tree = tree! {
+            dir: {
+                file
+                relative -> file
+                parent_relative -> "../dir/file"
+            }
+            absolute -> (&file)
+            broken -> missing
+        };
+        tree.write_at(&test_dir).unwrap();
+
+        let broken = tree.resolve_symlink_targets(&test_dir);
+        assert_eq!(broken, [PathBuf::from("broken")]);
+
+        let expected = tree! {
+            dir: {
+                file
+                relative -> (&file)
+                parent_relative -> (&file)
+            }
+            absolute -> (&file)
+            broken -> missing
+        };
+        assert_eq!(tree, expected);
+    }
+
+    #[test]
+    fn test_assert_symlink_free() {
+        let tree = tree! {
+            dir: {
+                file
+                inner_link -> target
+            }
+            link -> target
+        };
+
+        let expected = vec![PathBuf::from("dir/inner_link"), PathBuf::from("link")];
+        assert_eq!(tree.assert_symlink_free(), Err(expected));
+
+        assert_eq!(
+            FsTree::from_path_text("a/b/c").assert_symlink_free(),
+            Ok(())
+        );
+        assert_eq!(
+            FsTree::Symlink("target".into()).assert_symlink_free(),
+            Err(vec!["".into()])
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_all_paths_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = Path::new(OsStr::from_bytes(b"invalid_\xFF"));
+
+        let mut tree = tree! {
+            dir: {
+                file
+                link -> target
+            }
+        };
+        assert_eq!(tree.all_paths_utf8(), Ok(()));
+
+        tree.insert("dir/bad_link", FsTree::Symlink(invalid.into()));
+        tree.insert(invalid, FsTree::new_dir());
+        tree.insert(invalid.join("file"), FsTree::Regular);
+
+        let expected = vec![
+            PathBuf::from("dir/bad_link"),
+            invalid.to_path_buf(),
+            invalid.join("file"),
+        ];
+        assert_eq!(tree.all_paths_utf8(), Err(expected));
+    }
+
+    #[test]
+    fn test_get() {
+        let tree = FsTree::from_path_text("a/b/c");
+
+        assert_eq!(tree["a"], FsTree::from_path_text("b/c"));
+        assert_eq!(tree["a/b"], FsTree::from_path_text("c"));
+        assert_eq!(tree["a"]["b"], FsTree::from_path_text("c"));
+        assert_eq!(tree["a/b/c"], FsTree::Regular);
+        assert_eq!(tree["a/b"]["c"], FsTree::Regular);
+        assert_eq!(tree["a"]["b/c"], FsTree::Regular);
+        assert_eq!(tree["a"]["b"]["c"], FsTree::Regular);
+
+        // Paths are relative, so empty path returns the node itself
+        assert_eq!(tree[""], tree);
+        assert_eq!(tree[""], tree[""]);
+
+        // "."s are ignored
+        assert_eq!(tree["."], tree[""]);
+        assert_eq!(tree["././"], tree["."]);
+        assert_eq!(tree["././."], tree);
+        assert_eq!(tree["./a/."]["././b/./."], FsTree::from_path_text("c"));
+        assert_eq!(tree["./a/./b"]["c/."], FsTree::Regular);
+    }
+
+    #[test]
+    fn test_contains() {
+        let tree = FsTree::from_path_text("a/b/c");
+
+        assert!(tree.contains(""));
+        assert!(tree.contains("a"));
+        assert!(tree.contains("a/b/c"));
+        assert!(tree.contains("./a/./b"));
+        assert!(!tree.contains("b"));
+        assert!(!tree.contains("a/b/c/d"));
+    }
+
+    #[test]
+    fn test_common_ancestor() {
+        let tree = FsTree::from_path_text("a/b/c/d")
+            .merge(FsTree::from_path_text("a/b/e"))
+            .merge(FsTree::from_path_text("a/f"))
+            .merge(FsTree::from_path_text("g"));
+
+        let ancestor = |a, b| tree.common_ancestor(a, b);
+
+        assert_eq!(ancestor("a/b/c/d", "a/b/e"), Some("a/b".into()));
+        assert_eq!(ancestor("a/b/c/d", "a/f"), Some("a".into()));
+        assert_eq!(ancestor("a/b/c/d", "g"), Some("".into()));
+        assert_eq!(ancestor("a/b", "a/b/c/d"), Some("a/b".into()));
+        assert_eq!(ancestor("./a/b/e", "a/./b/e"), Some("a/b".into()));
+        assert_eq!(ancestor("a/f", "a/f"), Some("a".into()));
+        assert_eq!(ancestor("", "a/f"), Some("".into()));
+        assert_eq!(ancestor("a/b/e/x", "a/f"), None);
+        assert_eq!(ancestor("a", "missing"), None);
+
+        // A file root has no directories
+        assert_eq!(FsTree::Regular.common_ancestor("", ""), None);
+    }
+
+    #[test]
+    fn test_from_path_text_dir() {
+        let file = tree! { a: { b } };
+        let dir = tree! { a: { b: {} } };
+
+        // `from_path_text` ignores the trailing separator
+        assert_eq!(FsTree::from_path_text("a/b"), file);
+        assert_eq!(FsTree::from_path_text("a/b/"), file);
+
+        assert_eq!(FsTree::from_path_text_dir("a/b"), file);
+        assert_eq!(FsTree::from_path_text_dir("a/b/"), dir);
+        assert_eq!(FsTree::from_path_text_dir("a//b//"), dir);
+
+        assert_eq!(FsTree::from_path_text_dir("a/"), tree! { a: {} });
+        assert_eq!(FsTree::from_path_text_dir(""), FsTree::Regular);
+    }
+
+    #[test]
+    fn test_prefix_with() {
+        let tree = tree! {
+            dir: {
+                file
+            }
+            link -> dir
+        };
+
+        let result = tree.clone().prefix_with("a/b");
+        let paths: Vec<PathBuf> = result.paths().collect();
+        let expected = ["", "a", "a/b", "a/b/dir", "a/b/dir/file", "a/b/link"];
+        assert_eq!(paths, expected.map(PathBuf::from));
+
+        // `.`s are ignored
+        assert_eq!(tree.clone().prefix_with("./a/./b/."), result);
+
+        // Empty prefix
+        assert_eq!(tree.clone().prefix_with(""), tree);
+
+        // Non-directories can be nested too
+        assert_eq!(
+            FsTree::Regular.prefix_with("a/b/c"),
+            FsTree::from_path_text("a/b/c")
+        );
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        let tree = FsTree::from_path_text("a/b/c");
+        let strip_prefix = |prefix| tree.clone().strip_prefix(prefix);
+
+        assert_eq!(strip_prefix("a"), Some(FsTree::from_path_text("b/c")));
+        assert_eq!(strip_prefix("a/b"), Some(FsTree::from_path_text("c")));
+        assert_eq!(strip_prefix("a/b/c"), Some(FsTree::Regular));
+        assert_eq!(strip_prefix(""), Some(tree.clone()));
+
+        // "."s are ignored
+        assert_eq!(strip_prefix("."), Some(tree.clone()));
+        assert_eq!(strip_prefix("./a/./b/."), Some(FsTree::from_path_text("c")));
+
+        // Missing, or going through a non-directory
+        assert_eq!(strip_prefix("b"), None);
+        assert_eq!(strip_prefix("a/b/c/d"), None);
+
+        // Siblings outside of the prefix are dropped
+        let tree = tree! { a: { b: { c } other } other };
+        assert_eq!(tree.strip_prefix("a/b"), Some(tree! { c }));
+
+        // Inverse of `prefix_with`
+        let tree = FsTree::from_path_text("a/b/c");
+        for prefix in ["", "x", "x/y/z"] {
+            let prefixed = tree.clone().prefix_with(prefix);
+            assert_eq!(prefixed.strip_prefix(prefix), Some(tree.clone()));
+        }
+    }
+
+    // #[test]
+    // fn test_simple_merge() {
+    //     let left = FsTree::from_path_text(".config/i3/file");
+    //     let right = FsTree::from_path_text(".config/i3/folder/file");
+    //     let result = left.try_merge(right);
+
+    //     let expected = tree! {
+    //         ".config": {
+    //             i3: {
+    //                 file
+    //                 folder: {
+    //                     file
+    //                 }
+    //             }
+    //         }
+    //     };
+
+    //     assert_eq!(result, Some(expected));
+    // }
+
+    #[test]
+    fn test_conflicts_with() {
+        let tree = tree! {
+            dir: {
+                file
+                link -> target
+            }
+            other_file
+        };
+
+        assert!(!tree.conflicts_with(&tree));
+        assert!(!tree.conflicts_with(&FsTree::new_dir()));
+        assert!(!tree.conflicts_with(&tree! { dir: { new_file } new_dir: {} }));
+
+        // Symlinks conflict only if targets differ
+        assert!(!tree.conflicts_with(&tree! { dir: { link -> target } }));
+        assert!(tree.conflicts_with(&tree! { dir: { link -> other_target } }));
+
+        // Different types conflict
+        assert!(tree.conflicts_with(&tree! { dir: { file: {} } }));
+        assert!(tree.conflicts_with(&tree! { other_file -> target }));
+        assert!(tree.conflicts_with(&FsTree::Regular));
+
+        // Consistent with `try_merge`
+        let other = tree! { dir: { link -> other_target } };
+        assert_eq!(
+            tree.conflicts_with(&other),
+            tree.clone().try_merge(other).is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_path_text_checked() {
+        assert_eq!(
+            FsTree::from_path_text_checked("a/b"),
+            Ok(tree! { a: { b } })
+        );
+        assert_eq!(FsTree::from_path_text_checked("./a"), Ok(tree! { a }));
+        assert_eq!(
+            FsTree::from_path_text_checked("./a/./b"),
+            Ok(tree! { a: { b } })
+        );
+
+        let rejected = [
+            (
+                "../etc/passwd",
+                PathError::ParentDir("../etc/passwd".into()),
+            ),
+            (
+                "a/../../etc/passwd",
+                PathError::ParentDir("a/../../etc/passwd".into()),
+            ),
+            ("/etc/passwd", PathError::Absolute("/etc/passwd".into())),
+        ];
+        for (path, error) in rejected {
+            assert_eq!(FsTree::from_path_text_checked(path), Err(error));
+        }
+
+        // The unchecked version still accepts them
+        assert!(FsTree::from_path_text("../etc/passwd").get("..").is_some());
+    }
+
+    #[test]
+    fn test_same_shape_as() {
+        let tree = tree! {
+            a: {
+                b: {
+                    leaf
+                }
+            }
+            link -> target
+        };
+
+        assert!(tree.same_shape_as(&tree));
+        assert!(tree.same_shape_as(&tree! { a: { b: { leaf } } link -> elsewhere }));
+
+        // Only a leaf's type differs
+        let leaf_types = [
+            tree! { a: { b: { leaf: {} } } link -> target },
+            tree! { a: { b: { leaf -> target } } link -> target },
+            tree! { a: { b: { leaf } } link },
+        ];
+        for other in leaf_types {
+            assert!(!tree.same_shape_as(&other));
+            assert!(!other.same_shape_as(&tree));
+            // While the roots still have the same type
+            assert!(tree.is_same_type_as(&other));
+        }
+    }
+
+    #[test]
+    fn test_structural_eq() {
+        let tree = tree! {
+            dir: {
+                file
+                link -> a
+            }
+            empty: {}
+        };
+
+        // Differing symlink targets, only `PartialEq` sees a difference
+        let other = tree! {
+            dir: {
+                file
+                link -> b
+            }
+            empty: {}
+        };
+        assert!(tree.structural_eq(&other));
+        assert_ne!(tree, other);
+
+        assert!(tree.structural_eq(&tree));
+        assert!(FsTree::Symlink("a".into()).structural_eq(&FsTree::Symlink("b".into())));
+
+        let different = [
+            tree! { dir: { file link } empty: {} },
+            tree! { dir: { file link -> a } empty: { inner } },
+            tree! { dir: { file link -> a } },
+            tree! { dir: { file other -> a } empty: {} },
+        ];
+        for other in different {
+            assert!(!tree.structural_eq(&other));
+            assert!(!other.structural_eq(&tree));
+        }
+
+        // Only a leaf's type differs
+        assert!(!tree! { a }.structural_eq(&tree! { a: {} }));
+        assert!(!tree! { a: {} }.structural_eq(&tree! { a }));
+        assert!(!tree! { a }.structural_eq(&tree! { a -> b }));
+    }
+
+    #[test]
+    fn test_partial_eq_fails() {
+        let left = FsTree::from_path_text(".config/i3/a");
+        let right = FsTree::from_path_text(".config/i3/b");
+
+        assert_ne!(left, right);
+    }
+
+    #[test]
+    fn test_from_paths() {
+        let paths = ["a/b", "a/b/c", "a/d", "a/d", "e/f/g", "", "a/b"];
+        let result = FsTree::from_paths(paths);
+
+        let expected = tree! {
+            a: {
+                b: {
+                    c
+                }
+                d
+            }
+            e: {
+                f: {
+                    g
+                }
+            }
+        };
+        assert_eq!(result, expected);
+
+        // Order doesn't matter
+        let result: FsTree = paths.iter().rev().map(PathBuf::from).collect();
+        assert_eq!(result, expected);
+
+        assert_eq!(
+            FsTree::from_paths(["a/b/c"]),
+            FsTree::from_path_text("a/b/c")
+        );
+        assert_eq!(FsTree::from_paths([""; 0]), FsTree::new_dir());
+    }
+
+    #[test]
+    fn test_from_entries() {
+        let entries = [
+            ("z/deep/file", NodeKind::Regular),
+            ("a/link", NodeKind::Symlink("../z/deep/file".into())),
+            ("./a/empty", NodeKind::Directory),
+            ("a", NodeKind::Directory),
+            ("", NodeKind::Regular),
+        ];
+
+        let result = FsTree::from_entries(entries.map(|(path, kind)| (path.into(), kind)));
+
+        let expected = tree! {
+            a: {
+                empty: {}
+                link -> "../z/deep/file"
+            }
+            z: {
+                deep: {
+                    file
+                }
+            }
+        };
+        assert_eq!(result, expected);
+
+        // Same as the sorted pairs, in any order
+        let mut pairs = expected.clone().into_sorted_pairs();
+        pairs.reverse();
+        assert_eq!(FsTree::from_entries(pairs), expected);
+    }
+
+    #[test]
+    fn test_from_entries_collisions() {
+        let entries = [
+            ("a", NodeKind::Regular),
+            // Replaces the file in the way
+            ("a/b", NodeKind::Regular),
+            // Keeps the children
+            ("a", NodeKind::Directory),
+            // Later entries win
+            ("c", NodeKind::Directory),
+            ("c", NodeKind::Symlink("target".into())),
+            ("d", NodeKind::Symlink("target".into())),
+            ("d", NodeKind::Directory),
+        ];
+
+        let result = FsTree::from_entries(entries.map(|(path, kind)| (path.into(), kind)));
+
+        let expected = tree! {
+            a: {
+                b
+            }
+            c -> target
+            d: {}
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_common_root() {
+        let common_root = |paths: &[&str]| FsTree::common_root(paths.iter().map(Path::new));
+
+        assert_eq!(common_root(&[]), None);
+        assert_eq!(common_root(&["a/b/c"]), Some("a/b/c".into()));
+        assert_eq!(common_root(&["a/b/c", "a/b/c"]), Some("a/b/c".into()));
+        assert_eq!(common_root(&["a/b/c", "a/b/d", "a/b"]), Some("a/b".into()));
+        assert_eq!(common_root(&["/a/b", "/c"]), Some("/".into()));
+        assert_eq!(common_root(&["/a/b", "a/b"]), None);
+        assert_eq!(common_root(&["a/bc", "a/bd"]), Some("a".into()));
+    }
+
+    #[test]
+    fn test_minimal_cover() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                    d
+                }
+                e
+                empty: {}
+            }
+            f
+        };
+        let minimal_cover = |paths: &[&str]| tree.minimal_cover(paths.iter().map(Path::new));
+
+        assert_eq!(minimal_cover(&[]), Vec::<PathBuf>::new());
+        assert_eq!(
+            minimal_cover(&["a/b/c", "a/b/d"]),
+            ["a/b"].map(PathBuf::from)
+        );
+        assert_eq!(
+            minimal_cover(&["a/b/c", "a/b/d", "a/e"]),
+            ["a/b", "a/e"].map(PathBuf::from)
+        );
+        assert_eq!(
+            minimal_cover(&["a/b", "a/e", "a/empty"]),
+            ["a"].map(PathBuf::from)
+        );
+        assert_eq!(
+            minimal_cover(&["a/b/c", "f", "missing"]),
+            ["a/b/c", "f"].map(PathBuf::from)
+        );
+        assert_eq!(minimal_cover(&["a", "a/b/c", "f"]), [PathBuf::new()]);
+    }
+
+    #[test]
+    fn test_extend_paths_into() {
+        let trees = [
+            tree! { a: { b c } d -> e },
+            FsTree::Regular,
+            tree! { f: { g: { h } } },
+        ];
+
+        let mut result = vec![PathBuf::from("existing")];
+        for tree in &trees {
+            tree.extend_paths_into(&mut result);
+        }
+
+        let mut expected = vec![PathBuf::from("existing")];
+        for tree in &trees {
+            expected.extend(tree.paths());
+        }
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_apply_to_children() {
+        let mut tree = tree! {
+            a: {
+                b
+            }
+            c
+        };
+
+        tree.apply_to_children(|child| *child = FsTree::Symlink("target".into()));
+        assert_eq!(tree, tree! { a -> target c -> target });
+
+        let mut file = FsTree::Regular;
+        file.apply_to_children(|_| panic!("regular files have no children"));
+    }
+
+    #[test]
+    fn test_apply_to_all() {
+        let mut tree = tree! {
+            a: {
+                b: {
+                    c
+                }
+                link -> target
+            }
+            d
         };
 
-        // Corner case: if `.`, ignore it and call again with the rest
-        if popped == Path::new(".") {
-            return self.get_mut(path_rest);
-        }
+        let mut visited = 0;
+        tree.apply_to_all(|_| visited += 1);
+        assert_eq!(visited, tree.nodes().count());
 
-        self.children_mut()?
-            .get_mut(popped)
-            .and_then(|child| child.get_mut(path_rest))
+        // Replacing a directory skips its old children
+        let mut visited = 0;
+        tree.apply_to_all(|node| {
+            visited += 1;
+            if node.get("c").is_some() {
+                *node = FsTree::Regular;
+            }
+        });
+        assert_eq!(visited, 5);
+        assert_eq!(tree, tree! { a: { b link -> target } d });
     }
 
-    /// Inserts a node at the given path.
-    ///
-    /// # Panics:
-    ///
-    /// - If there are no directories up to the path node in order to insert it.
-    /// - If path is empty.
-    pub fn insert(&mut self, path: impl AsRef<Path>, node: Self) {
-        use FsTree::*;
-
-        let mut iter = path.as_ref().iter();
+    #[test]
+    fn test_prune_empty_dirs() {
+        let mut tree = tree! {
+            a: {
+                b: {
+                    c: {}
+                }
+                file
+            }
+            empty: {}
+            link -> empty
+        };
+        tree.prune_empty_dirs();
 
-        let Some(node_name) = iter.next_back().map(Path::new) else {
-            *self = node;
-            return;
+        let expected = tree! {
+            a: {
+                file
+            }
+            link -> empty
         };
+        assert_eq!(tree, expected);
 
-        let mut tree = self;
+        let mut tree = FsTree::new_dir();
+        tree.prune_empty_dirs();
+        assert_eq!(tree, FsTree::new_dir());
+    }
 
-        // Traverse tree
-        for next in iter {
-            // Give a better error message than the one below
-            if !tree.is_dir() {
-                panic!(
-                    "Failed to insert node, while traversing, one of the parent directories \
-                    ({next:?}) isn't a directory, but a {}",
-                    tree.variant_str()
-                );
+    #[test]
+    fn test_retain() {
+        let tree = tree! {
+            src: {
+                "lib.rs"
+                "main.rs"
+                "data.json"
+                nested: {
+                    "mod.rs"
+                }
+            }
+            docs: {
+                "readme.md"
             }
+            empty: {}
+            "build.rs"
+        };
 
-            tree = if let Some(tree) = tree.get_mut(next) {
-                tree
-            } else {
-                panic!("Failed to insert node, parent directory {next:?} doesn't exist");
-            };
-        }
+        let is_rust_file = |path: &Path, node: &FsTree| -> bool {
+            node.is_dir() || path.extension().is_some_and(|extension| extension == "rs")
+        };
 
-        match tree {
-            Regular | Symlink(_) => {
-                panic!(
-                    "Failed to insert node, parent directory is not a directory, but a {}",
-                    tree.variant_str(),
-                );
-            },
-            Directory(children) => {
-                children.insert(node_name.into(), node);
-            },
-        }
-    }
-}
+        let mut result = tree.clone();
+        result.retain(false, is_rust_file);
+        let expected = tree! {
+            src: {
+                "lib.rs"
+                "main.rs"
+                nested: {
+                    "mod.rs"
+                }
+            }
+            empty: {}
+            "build.rs"
+        };
+        assert_eq!(result, expected);
 
-#[cfg(feature = "libc-file-type")]
-impl FsTree {
-    /// Returns the file type equivalent [`libc::mode_t`] value.
-    pub fn as_mode_t(&self) -> libc::mode_t {
-        match self {
-            Self::Regular => libc::S_IFREG,
-            Self::Directory(_) => libc::S_IFDIR,
-            Self::Symlink(_) => libc::S_IFCHR,
-        }
+        let mut result = tree.clone();
+        result.retain(true, is_rust_file);
+        let expected = tree! {
+            src: {
+                "lib.rs"
+                "main.rs"
+                nested: {
+                    "mod.rs"
+                }
+            }
+            docs: {}
+            empty: {}
+            "build.rs"
+        };
+        assert_eq!(result, expected);
+
+        // Removing a directory removes its descendants, without testing them
+        let mut result = tree.clone();
+        result.retain(true, |path, _| {
+            assert!(path == Path::new("src") || !path.starts_with("src"));
+            path != Path::new("src")
+        });
+        assert_eq!(result, tree! { docs: { "readme.md" } empty: {} "build.rs" });
     }
-}
 
-impl<P> Index<P> for FsTree
-where
-    P: AsRef<Path>,
-{
-    type Output = FsTree;
+    #[test]
+    fn test_partition() {
+        let tree = tree! {
+            a: {
+                b: {
+                    file1
+                    link1 -> target
+                }
+                c: {
+                    link2 -> target
+                }
+                empty: {}
+            }
+            file2
+        };
 
-    fn index(&self, path: P) -> &Self::Output {
-        self.get(path.as_ref())
-            .unwrap_or_else(|| panic!("no node found for path '{}'", path.as_ref().display()))
-    }
-}
+        let (symlinks, rest) = tree.partition(|_, node| node.is_symlink());
 
-#[cfg(test)]
-mod tests {
-    use std::{io, path::Path};
+        let expected_symlinks = tree! {
+            a: {
+                b: {
+                    link1 -> target
+                }
+                c: {
+                    link2 -> target
+                }
+            }
+        };
+        let expected_rest = tree! {
+            a: {
+                b: {
+                    file1
+                }
+                empty: {}
+            }
+            file2
+        };
 
-    use pretty_assertions::{assert_eq, assert_ne};
+        assert_eq!(symlinks, expected_symlinks);
+        assert_eq!(rest, expected_rest);
 
-    use super::*;
-    use crate::tree;
+        // Paths are relative to the root
+        let tree = tree! { a: { b: { c } d } e };
+        let (matching, rest) = tree.partition(|path, _| path.starts_with("a/b"));
+        assert_eq!(matching, tree! { a: { b: { c } } });
+        assert_eq!(rest, tree! { a: { d } e });
 
-    fn testdir() -> io::Result<(tempfile::TempDir, &'static Path)> {
-        let dir = tempfile::tempdir()?;
-        let path = dir.path().to_path_buf().into_boxed_path();
-        Ok((dir, Box::leak(path)))
+        // The root is tested if it isn't a directory
+        let (matching, rest) = FsTree::Regular.partition(|path, _| path == Path::new(""));
+        assert_eq!(matching, FsTree::Regular);
+        assert_eq!(rest, FsTree::new_dir());
     }
 
-    // #[test]
-    // fn test_diff() {
-    //     let left = FsTree::from_path_text(".config/i3/file").unwrap();
-    //     let right = FsTree::from_path_text(".config/i3/folder/file/oie").unwrap();
-    //     left.diff(&right);
-    //     panic!();
-    // }
-
     #[test]
-    fn test_insert_basic() {
-        let mut tree = FsTree::new_dir();
-
-        let paths = ["a", "a/b", "a/b/c", "a/b/c/d", "a/b/c/d/e"];
-        for path in paths {
-            tree.insert(path, FsTree::new_dir());
-        }
+    fn test_without_regular_files() {
+        let tree = tree! {
+            a: {
+                b: {
+                    file
+                }
+                link -> target
+                file
+            }
+            file
+        };
 
-        tree.insert("a/b/c/d/e/f", FsTree::Regular);
+        let mut result = tree.without_regular_files();
+        assert_eq!(result, tree! { a: { b: {} link -> target } });
 
-        let expected = tree! {
-            a: { b: { c: { d: { e: { f } } } } }
-        };
+        result.prune_empty_dirs();
+        assert_eq!(result, tree! { a: { link -> target } });
 
-        assert_eq!(tree, expected);
+        assert_eq!(FsTree::Regular.without_regular_files(), FsTree::Regular);
     }
 
-    #[rustfmt::skip]
     #[test]
-    fn test_insert_complete() {
-        let result = {
-            let mut tree = FsTree::new_dir();
-            tree.insert("config1", FsTree::Regular);
-            tree.insert("config2", FsTree::Regular);
-            tree.insert("outer_dir", FsTree::new_dir());
-            tree.insert("outer_dir/file1", FsTree::Regular);
-            tree.insert("outer_dir/file2", FsTree::Regular);
-            tree.insert("outer_dir/inner_dir", FsTree::new_dir());
-            tree.insert("outer_dir/inner_dir/inner1", FsTree::Regular);
-            tree.insert("outer_dir/inner_dir/inner2", FsTree::Regular);
-            tree.insert("outer_dir/inner_dir/inner3", FsTree::Regular);
-            tree.insert("outer_dir/inner_dir/inner_link", FsTree::Symlink("inner_target".into()));
-            tree.insert("link", FsTree::Symlink("target".into()));
-            tree.insert("config3", FsTree::Regular);
-            tree
+    fn test_sorted_pairs_round_trip() {
+        let tree = tree! {
+            "a-b"
+            a: {
+                b: {
+                    c
+                }
+                link -> target
+            }
+            "a.txt"
         };
 
-        let expected = tree! {
-            config1
-            config2
-            outer_dir: {
-                file1
-                file2
-                inner_dir: {
-                    inner1
-                    inner2
-                    inner3
-                    inner_link -> inner_target
+        let pairs = tree.clone().into_sorted_pairs();
+
+        let mut sorted = pairs.clone();
+        sorted.sort();
+        assert_eq!(pairs, sorted);
+
+        assert_eq!(FsTree::from_sorted_pairs(pairs), tree);
+    }
+
+    #[test]
+    fn test_for_each_with_components_matches_paths() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
                 }
+                link -> target
             }
-            link -> target
-            config3
+            d
         };
 
-        assert_eq!(result, expected);
+        let mut paths = vec![];
+        tree.for_each_with_components(|components, node| {
+            paths.push(components.iter().collect::<PathBuf>());
+            assert_eq!(node, &tree[paths.last().unwrap()]);
+        });
+
+        assert_eq!(paths, tree.paths().collect::<Vec<_>>());
     }
 
     #[test]
-    fn test_write_at() {
-        let (_dropper, test_dir) = testdir().unwrap();
-
+    fn test_for_each_path_matches_paths() {
         let tree = tree! {
             a: {
                 b: {
                     c
-                    empty: {}
-                    link -> target
+                    d -> c
                 }
+                e: {}
             }
+            f
         };
 
-        tree.write_at(test_dir).unwrap();
+        let mut visited = vec![];
+        tree.for_each_path(|path| visited.push(path.to_path_buf()));
 
-        let result = FsTree::symlink_read_at(test_dir).unwrap();
+        assert_eq!(visited, tree.paths().collect::<Vec<_>>());
 
-        assert_eq!(result, tree);
+        let mut visited = vec![];
+        FsTree::Regular.for_each_path(|path| visited.push(path.to_path_buf()));
+        assert_eq!(visited, [PathBuf::new()]);
     }
 
     #[test]
-    fn test_get() {
-        let tree = FsTree::from_path_text("a/b/c");
+    fn test_walk_stops_at_first_match() {
+        let tree = tree! {
+            a: {
+                b: {
+                    "match1"
+                }
+                "match2"
+            }
+            "match3"
+        };
 
-        assert_eq!(tree["a"], FsTree::from_path_text("b/c"));
-        assert_eq!(tree["a/b"], FsTree::from_path_text("c"));
-        assert_eq!(tree["a"]["b"], FsTree::from_path_text("c"));
-        assert_eq!(tree["a/b/c"], FsTree::Regular);
-        assert_eq!(tree["a/b"]["c"], FsTree::Regular);
-        assert_eq!(tree["a"]["b/c"], FsTree::Regular);
-        assert_eq!(tree["a"]["b"]["c"], FsTree::Regular);
+        let mut visited = vec![];
+        let stopped = tree.walk(|path, _| {
+            visited.push(path.to_path_buf());
 
-        // Paths are relative, so empty path returns the node itself
-        assert_eq!(tree[""], tree);
-        assert_eq!(tree[""], tree[""]);
+            if path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("match"))
+            {
+                WalkAction::Stop
+            } else {
+                WalkAction::Continue
+            }
+        });
 
-        // "."s are ignored
-        assert_eq!(tree["."], tree[""]);
-        assert_eq!(tree["././"], tree["."]);
-        assert_eq!(tree["././."], tree);
-        assert_eq!(tree["./a/."]["././b/./."], FsTree::from_path_text("c"));
-        assert_eq!(tree["./a/./b"]["c/."], FsTree::Regular);
+        assert!(stopped);
+        assert_eq!(visited, ["", "a", "a/b", "a/b/match1"].map(PathBuf::from));
     }
 
-    // #[test]
-    // fn test_simple_merge() {
-    //     let left = FsTree::from_path_text(".config/i3/file");
-    //     let right = FsTree::from_path_text(".config/i3/folder/file");
-    //     let result = left.try_merge(right);
+    #[test]
+    fn test_walk_skips_subtree() {
+        let tree = tree! {
+            a: {
+                file1
+            }
+            node_modules: {
+                dep: {
+                    file2
+                }
+            }
+            z: {
+                file3
+            }
+        };
 
-    //     let expected = tree! {
-    //         ".config": {
-    //             i3: {
-    //                 file
-    //                 folder: {
-    //                     file
-    //                 }
-    //             }
-    //         }
-    //     };
+        let mut visited = vec![];
+        let stopped = tree.walk(|path, _| {
+            visited.push(path.to_path_buf());
 
-    //     assert_eq!(result, Some(expected));
-    // }
+            if path.ends_with("node_modules") {
+                WalkAction::SkipSubtree
+            } else {
+                WalkAction::Continue
+            }
+        });
+
+        assert!(!stopped);
+        let expected = ["", "a", "a/file1", "node_modules", "z", "z/file3"];
+        assert_eq!(visited, expected.map(PathBuf::from));
+
+        // Skipping the root visits nothing else, skipping a leaf is the same as continuing
+        let mut count = 0;
+        tree.walk(|_, _| {
+            count += 1;
+            WalkAction::SkipSubtree
+        });
+        assert_eq!(count, 1);
+
+        let mut count = 0;
+        tree.walk(|_, node| {
+            count += 1;
+            if node.is_dir() {
+                WalkAction::Continue
+            } else {
+                WalkAction::SkipSubtree
+            }
+        });
+        assert_eq!(count, tree.len_all());
+    }
 
     #[test]
-    fn test_partial_eq_fails() {
-        let left = FsTree::from_path_text(".config/i3/a");
-        let right = FsTree::from_path_text(".config/i3/b");
+    fn test_walk_with_parent() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                }
+                link -> target
+            }
+            d
+        };
 
-        assert_ne!(left, right);
+        let mut visited = vec![];
+        tree.walk_with_parent(|parent, path, node| {
+            visited.push((parent.cloned(), path.to_path_buf(), node.clone()));
+        });
+
+        let expected = vec![
+            (None, "".into(), tree.clone()),
+            (Some(tree.clone()), "a".into(), tree["a"].clone()),
+            (Some(tree["a"].clone()), "a/b".into(), tree["a/b"].clone()),
+            (Some(tree["a/b"].clone()), "a/b/c".into(), FsTree::Regular),
+            (
+                Some(tree["a"].clone()),
+                "a/link".into(),
+                FsTree::Symlink("target".into()),
+            ),
+            (Some(tree.clone()), "d".into(), FsTree::Regular),
+        ];
+
+        assert_eq!(visited, expected);
     }
 }
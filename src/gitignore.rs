@@ -0,0 +1,171 @@
+//! Minimal `.gitignore` rules, used by [`FsTree::read_at_gitignore`].
+//!
+//! [`FsTree::read_at_gitignore`]: crate::FsTree::read_at_gitignore
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    glob::Glob,
+    utils::{self, fs},
+    Result,
+};
+
+/// The rules of a single `.gitignore` file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Gitignore {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: Glob,
+    /// Starts with `!`, re-includes what previous rules ignored.
+    negated: bool,
+    /// Ends with `/`, only matches directories.
+    only_dirs: bool,
+}
+
+impl Gitignore {
+    /// Parses the contents of a `.gitignore` file.
+    pub(crate) fn parse(text: &str) -> Self {
+        let rules = text.lines().filter_map(parse_rule).collect();
+        Self { rules }
+    }
+
+    /// Reads and parses the `.gitignore` file at `path`, if it's a file.
+    pub(crate) fn read_at(path: &Path) -> Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let text = fs::read_to_string(path).map_err(|error| utils::io_error_at(error, path))?;
+        Ok(Some(Self::parse(&text)))
+    }
+
+    /// Checks the rules against `path`, relative to the `.gitignore` directory.
+    ///
+    /// The last matching rule wins, returns `Some(true)` if ignored, `Some(false)` if re-included,
+    /// or `None` if no rule matches.
+    pub(crate) fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.only_dirs) && rule.glob.matches(path))
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end();
+
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+
+    let (only_dirs, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+
+    // A separator at the start or middle anchors the pattern to the `.gitignore` directory,
+    // otherwise, it matches at any depth
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let glob = if anchored {
+        Glob::new(pattern)
+    } else {
+        Glob::new(&format!("**/{pattern}"))
+    };
+
+    Some(Rule {
+        glob,
+        negated,
+        only_dirs,
+    })
+}
+
+/// Checks if `path` is ignored by the `.gitignore`s of its ancestors, keyed by directory path.
+///
+/// Deeper `.gitignore`s take precedence over the ones above them.
+pub(crate) fn is_ignored(
+    ignores: &BTreeMap<PathBuf, Gitignore>,
+    path: &Path,
+    is_dir: bool,
+) -> bool {
+    path.ancestors()
+        .skip(1)
+        .filter_map(|dir| {
+            let gitignore = ignores.get(dir)?;
+            let relative = path.strip_prefix(dir).expect("dir is an ancestor of path");
+            gitignore.matched(relative, is_dir)
+        })
+        .next()
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gitignore_matched() {
+        let gitignore = Gitignore::parse(
+            "\
+# Comment
+*.log
+!keep.log
+/root_only
+build/
+docs/*.html
+\\#hash
+",
+        );
+
+        let cases = [
+            ("a.log", false, Some(true)),
+            ("nested/a.log", false, Some(true)),
+            ("keep.log", false, Some(false)),
+            ("root_only", false, Some(true)),
+            ("nested/root_only", false, None),
+            ("build", true, Some(true)),
+            ("nested/build", true, Some(true)),
+            ("build", false, None),
+            ("docs/index.html", false, Some(true)),
+            ("nested/docs/index.html", false, None),
+            ("#hash", false, Some(true)),
+            ("other", false, None),
+        ];
+
+        for (path, is_dir, expected) in cases {
+            let result = gitignore.matched(Path::new(path), is_dir);
+            assert_eq!(result, expected, "path {path:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_ignored_stacks() {
+        let ignores = BTreeMap::from([
+            (PathBuf::new(), Gitignore::parse("*.tmp\n")),
+            (PathBuf::from("a"), Gitignore::parse("!*.tmp\nsecret\n")),
+        ]);
+
+        assert!(is_ignored(&ignores, Path::new("file.tmp"), false));
+        assert!(is_ignored(&ignores, Path::new("b/file.tmp"), false));
+        assert!(!is_ignored(&ignores, Path::new("a/file.tmp"), false));
+        assert!(is_ignored(&ignores, Path::new("a/b/secret"), false));
+        assert!(!is_ignored(&ignores, Path::new("secret"), false));
+    }
+}
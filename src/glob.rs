@@ -0,0 +1,282 @@
+//! Glob pattern matching for tree paths.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::FsTree;
+
+/// A glob pattern, matched against paths component by component.
+///
+/// Supported syntax:
+/// - `*` matches any sequence of characters inside of a component.
+/// - `?` matches a single character.
+/// - `[abc]`, `[a-z]` and `[!a-z]` match a single character in (or not in) the class.
+/// - `**` as a whole component matches zero or more components.
+///
+/// An unterminated `[` is matched literally.
+#[derive(Debug, Clone)]
+pub(crate) struct Glob {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    /// `**`, matches any number of components.
+    AnyDepth,
+    /// Matches a single component.
+    Component(Vec<Token>),
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    AnyString,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Self {
+        let segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .map(|segment| {
+                match segment {
+                    "**" => Segment::AnyDepth,
+                    _ => Segment::Component(parse_tokens(segment)),
+                }
+            })
+            .collect();
+
+        Self { segments }
+    }
+
+    /// Checks if the whole `path` matches the pattern.
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        let components: Vec<String> = path
+            .components()
+            .filter_map(|component| {
+                match component {
+                    Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        matches_segments(&self.segments, &components)
+    }
+}
+
+fn parse_tokens(segment: &str) -> Vec<Token> {
+    let chars: Vec<char> = segment.chars().collect();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        let token = match chars[index] {
+            '*' => Token::AnyString,
+            '?' => Token::AnyChar,
+            '[' => {
+                match parse_class(&chars[index + 1..]) {
+                    Some((class, consumed)) => {
+                        index += consumed;
+                        class
+                    },
+                    None => Token::Literal('['),
+                }
+            },
+            ch => Token::Literal(ch),
+        };
+
+        index += 1;
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Parses the class after the opening `[`, returning it and the number of chars consumed.
+fn parse_class(chars: &[char]) -> Option<(Token, usize)> {
+    let negated = matches!(chars.first(), Some('!' | '^'));
+    let start = usize::from(negated);
+
+    // A `]` right at the start is part of the class
+    let end = chars
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, ch)| **ch == ']')
+        .map(|(index, _)| index)?;
+
+    let body = &chars[start..end];
+    let mut ranges = vec![];
+    let mut index = 0;
+
+    while index < body.len() {
+        if index + 2 < body.len() && body[index + 1] == '-' {
+            ranges.push((body[index], body[index + 2]));
+            index += 3;
+        } else {
+            ranges.push((body[index], body[index]));
+            index += 1;
+        }
+    }
+
+    Some((Token::Class { negated, ranges }, end + 1))
+}
+
+fn matches_segments<S: AsRef<str>>(segments: &[Segment], components: &[S]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((Segment::AnyDepth, rest)) => {
+            (0..=components.len()).any(|skip| matches_segments(rest, &components[skip..]))
+        },
+        Some((Segment::Component(tokens), rest)) => {
+            match components.split_first() {
+                Some((component, components)) => {
+                    let chars: Vec<char> = component.as_ref().chars().collect();
+                    matches_tokens(tokens, &chars) && matches_segments(rest, components)
+                },
+                None => false,
+            }
+        },
+    }
+}
+
+fn matches_tokens(tokens: &[Token], chars: &[char]) -> bool {
+    match tokens.split_first() {
+        None => chars.is_empty(),
+        Some((Token::AnyString, rest)) => {
+            (0..=chars.len()).any(|skip| matches_tokens(rest, &chars[skip..]))
+        },
+        Some((token, rest)) => {
+            match chars.split_first() {
+                Some((ch, chars)) => matches_char(token, *ch) && matches_tokens(rest, chars),
+                None => false,
+            }
+        },
+    }
+}
+
+fn matches_char(token: &Token, ch: char) -> bool {
+    match token {
+        Token::Literal(expected) => *expected == ch,
+        Token::AnyChar => true,
+        Token::AnyString => unreachable!("handled by the caller"),
+        Token::Class { negated, ranges } => {
+            let in_class = ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&ch));
+            in_class != *negated
+        },
+    }
+}
+
+impl FsTree {
+    /// Counts the nodes whose path (relative to the root) matches the glob `pattern`.
+    ///
+    /// The root has no path and is never counted.
+    ///
+    /// Supported syntax:
+    /// - `*` matches any sequence of characters inside of a path component.
+    /// - `?` matches a single character.
+    /// - `[abc]`, `[a-z]` and `[!a-z]` match a single character in (or not in) the class.
+    /// - `**` as a whole component matches zero or more components.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     src: {
+    ///         "lib.rs"
+    ///         "test_lib.rs"
+    ///     }
+    ///     tests: {
+    ///         "test_integration.rs"
+    ///     }
+    /// };
+    ///
+    /// assert_eq!(tree.count_glob("**/test_*.rs"), 2);
+    /// assert_eq!(tree.count_glob("src/*"), 2);
+    /// assert_eq!(tree.count_glob("*"), 2);
+    /// ```
+    pub fn count_glob(&self, pattern: &str) -> usize {
+        let glob = Glob::new(pattern);
+        let mut count = 0;
+        self.__count_glob(&glob, &mut PathBuf::new(), &mut count);
+        count
+    }
+
+    fn __count_glob(&self, glob: &Glob, path: &mut PathBuf, count: &mut usize) {
+        if let Some(children) = self.children() {
+            for (name, child) in children {
+                path.push(name);
+
+                if glob.matches(path) {
+                    *count += 1;
+                }
+                child.__count_glob(glob, path, count);
+
+                path.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree;
+
+    #[test]
+    fn test_glob_matches() {
+        let cases = [
+            ("*.rs", "main.rs", true),
+            ("*.rs", "src/main.rs", false),
+            ("**/*.rs", "main.rs", true),
+            ("**/*.rs", "src/bin/main.rs", true),
+            ("src/**", "src", true),
+            ("src/**", "src/a/b", true),
+            ("src/**/b", "src/a/b", true),
+            ("src/**/b", "src/a/c", false),
+            ("?.txt", "a.txt", true),
+            ("?.txt", "ab.txt", false),
+            ("[a-c]*", "banana", true),
+            ("[!a-c]*", "banana", false),
+            ("[]]", "]", true),
+            ("[", "[", true),
+            ("a*b*c", "axxbyyc", true),
+            ("a*b*c", "axxbyy", false),
+            ("./src/*", "src/lib.rs", true),
+        ];
+
+        for (pattern, path, expected) in cases {
+            let result = Glob::new(pattern).matches(Path::new(path));
+            assert_eq!(result, expected, "pattern {pattern:?} against {path:?}");
+        }
+    }
+
+    #[test]
+    fn test_count_glob() {
+        let tree = tree! {
+            src: {
+                "lib.rs"
+                "test_lib.rs"
+                nested: {
+                    "test_nested.rs"
+                }
+            }
+            "test_root.rs"
+            "test_dir.rs": {}
+        };
+
+        assert_eq!(tree.count_glob("**/test_*.rs"), 4);
+        assert_eq!(tree.count_glob("**"), tree.len_all() - 1);
+        assert_eq!(tree.count_glob("src/*"), 3);
+        assert_eq!(tree.count_glob("missing"), 0);
+    }
+}
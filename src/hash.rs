@@ -0,0 +1,299 @@
+//! Hashing of tree structures.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    ffi::OsStr,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use crate::FsTree;
+
+impl FsTree {
+    /// Computes a hash of the subtree of each directory, keyed by the directory path.
+    ///
+    /// Two directories with the same hash have (with very high probability) identical subtrees,
+    /// this is useful for incremental syncing: when comparing the maps of two trees, subtrees with
+    /// equal hashes can be skipped entirely.
+    ///
+    /// Hashes are computed bottom-up in a single pass, each directory hash combines its children
+    /// names and their hashes.
+    ///
+    /// Hashes are only comparable within the same program, they aren't guaranteed to be stable
+    /// across Rust versions.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// use fs_tree::tree;
+    ///
+    /// let left = tree! {
+    ///     same: { file }
+    ///     changed: { file }
+    /// };
+    /// let right = tree! {
+    ///     same: { file }
+    ///     changed: { other_file }
+    /// };
+    ///
+    /// let left = left.subtree_hashes();
+    /// let right = right.subtree_hashes();
+    ///
+    /// assert_eq!(left[Path::new("same")], right[Path::new("same")]);
+    /// assert_ne!(left[Path::new("changed")], right[Path::new("changed")]);
+    /// assert_ne!(left[Path::new("")], right[Path::new("")]);
+    /// ```
+    pub fn subtree_hashes(&self) -> BTreeMap<PathBuf, u64> {
+        let mut hashes = BTreeMap::new();
+        self.__subtree_hashes(&mut PathBuf::new(), &mut hashes);
+        hashes
+    }
+
+    fn __subtree_hashes(&self, path: &mut PathBuf, hashes: &mut BTreeMap<PathBuf, u64>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        match self {
+            Self::Regular => 0_u8.hash(&mut hasher),
+            Self::Directory(children) => {
+                1_u8.hash(&mut hasher);
+
+                for (name, child) in children {
+                    path.push(name);
+                    let child_hash = child.__subtree_hashes(path, hashes);
+                    path.pop();
+
+                    name.hash(&mut hasher);
+                    child_hash.hash(&mut hasher);
+                }
+            },
+            Self::Symlink(target) => {
+                2_u8.hash(&mut hasher);
+                target.hash(&mut hasher);
+            },
+        }
+
+        let hash = hasher.finish();
+
+        if self.is_dir() {
+            hashes.insert(path.clone(), hash);
+        }
+
+        hash
+    }
+
+    /// Computes a hash of the whole tree that is stable across runs, machines and Rust versions.
+    ///
+    /// Unlike the derived [`Hash`], which depends on the hasher and on `std` implementation
+    /// details, this hashes a fixed encoding of the tree (names, node types and symlink targets,
+    /// in sorted order) with 64-bit [FNV-1a], so it can be persisted, like for cache keys.
+    ///
+    /// Names and targets are hashed as raw bytes on Unix, and as UTF-8 elsewhere (invalid Unicode
+    /// is replaced by `U+FFFD`).
+    ///
+    /// This isn't a cryptographic hash, don't use it to detect malicious changes.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! { link -> target };
+    ///
+    /// assert_eq!(tree.content_hash(), tree.clone().content_hash());
+    /// assert_ne!(tree.content_hash(), tree! { link -> other }.content_hash());
+    /// ```
+    ///
+    /// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        self.__content_hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn __content_hash(&self, hasher: &mut Fnv1a) {
+        match self {
+            Self::Regular => hasher.write(&[0]),
+            Self::Directory(children) => {
+                hasher.write(&[1]);
+                hasher.write_len(children.len());
+
+                for (name, child) in children {
+                    hasher.write_path(name);
+                    child.__content_hash(hasher);
+                }
+            },
+            Self::Symlink(target) => {
+                hasher.write(&[2]);
+                hasher.write_path(target);
+            },
+        }
+    }
+}
+
+/// 64-bit FNV-1a, with a fixed encoding for lengths, used by [`FsTree::content_hash`].
+///
+/// Doesn't implement [`Hasher`], because its `write_*` methods use the native endianness.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    // Component-wise, like `Path`'s `Eq`, so `a/b` and `a/b/` hash the same, and
+    // length-prefixed, so consecutive paths can't be confused
+    fn write_path(&mut self, path: &Path) {
+        self.write_len(path.components().count());
+
+        for component in path.components() {
+            let bytes = os_str_bytes(component.as_os_str());
+            self.write_len(bytes.len());
+            self.write(&bytes);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(unix)]
+fn os_str_bytes(string: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Cow::Borrowed(string.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes(string: &OsStr) -> Cow<'_, [u8]> {
+    match string.to_string_lossy() {
+        Cow::Borrowed(string) => Cow::Borrowed(string.as_bytes()),
+        Cow::Owned(string) => Cow::Owned(string.into_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use crate::{tree, FsTree};
+
+    #[test]
+    fn test_subtree_hashes() {
+        let left = tree! {
+            a: {
+                b: {
+                    file
+                }
+                link -> target
+            }
+            empty: {}
+        };
+        let right = tree! {
+            a: {
+                b: {
+                    file
+                }
+                link -> other_target
+            }
+            empty: {}
+        };
+
+        let left_hashes = left.subtree_hashes();
+        let right_hashes = right.subtree_hashes();
+
+        // Only directories are included
+        let keys: Vec<_> = left_hashes
+            .keys()
+            .map(|path| path.to_str().unwrap())
+            .collect();
+        assert_eq!(keys, ["", "a", "a/b", "empty"]);
+
+        assert_eq!(
+            left_hashes[Path::new("a/b")],
+            right_hashes[Path::new("a/b")]
+        );
+        assert_eq!(
+            left_hashes[Path::new("empty")],
+            right_hashes[Path::new("empty")]
+        );
+        assert_ne!(left_hashes[Path::new("a")], right_hashes[Path::new("a")]);
+        assert_ne!(left_hashes[Path::new("")], right_hashes[Path::new("")]);
+
+        // A file and an empty directory with the same name differ
+        assert_ne!(
+            tree! { x }.subtree_hashes()[Path::new("")],
+            tree! { x: {} }.subtree_hashes()[Path::new("")]
+        );
+        assert_eq!(left.subtree_hashes(), left_hashes);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let tree = tree! {
+            a: {
+                b: {
+                    file
+                }
+                link -> target
+            }
+            empty: {}
+        };
+
+        assert_eq!(tree.content_hash(), tree.clone().content_hash());
+
+        let changed_target = tree! {
+            a: {
+                b: {
+                    file
+                }
+                link -> other_target
+            }
+            empty: {}
+        };
+        assert_ne!(tree.content_hash(), changed_target.content_hash());
+
+        // Equal paths hash the same, like `==`
+        assert_eq!(
+            tree! { link -> "a/b" }.content_hash(),
+            tree! { link -> "a/b/" }.content_hash()
+        );
+
+        let different = [
+            tree! { x },
+            tree! { x: {} },
+            tree! { x -> "" },
+            tree! { x y },
+            tree! { xy },
+            tree! { x: { y } },
+        ];
+        for (i, left) in different.iter().enumerate() {
+            for right in &different[i + 1..] {
+                assert_ne!(left.content_hash(), right.content_hash());
+            }
+        }
+
+        // Pinned values, these must never change
+        assert_eq!(FsTree::Regular.content_hash(), 0xaf63_bd4c_8601_b7df);
+        assert_eq!(tree! { a -> b }.content_hash(), 0xa8c8_fe60_8b6c_4930);
+    }
+}
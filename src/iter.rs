@@ -1,11 +1,31 @@
 //! Iterators for [`FsTree`].
 //!
-//! Iterators traverse in [Depth-First Order](https://en.wikipedia.org/wiki/Binary_tree#Depth-first_order).
+//! Iterators traverse in [Depth-First Order](https://en.wikipedia.org/wiki/Binary_tree#Depth-first_order),
+//! the ones that support filters can be switched to
+//! [Breadth-First Order](https://en.wikipedia.org/wiki/Breadth-first_search) with
+//! `.breadth_first(true)`, and filtered by path with `.glob(pattern)`.
 //!
-//! There are three [`FsTree`] methods for creating an iterator:
+//! There are four [`FsTree`] methods for creating an iterator:
 //! 1. [`Iter`](iter::Iter) from [`.iter()`](FsTree::iter) yields `(&FsTree, PathBuf)`.
 //! 2. [`NodesIter`](iter::NodesIter) from [`.nodes()`](FsTree::nodes) yields `&FsTree`.
 //! 3. [`PathsIter`](iter::PathsIter) from [`.paths()`](FsTree::paths) yields `PathBuf`.
+//! 4. [`NamedNodesIter`](iter::NamedNodesIter) from
+//!    [`.nodes_with_names()`](FsTree::nodes_with_names) yields `(&Path, &FsTree)`, where the path
+//!    is just the node name, borrowed from the tree, so it doesn't allocate.
+//!
+//! [`Iter`](iter::Iter) can be adapted with [`.with_depth()`](iter::Iter::with_depth) into
+//! [`DepthIter`](iter::DepthIter), yielding `(usize, &FsTree, PathBuf)`, where the first element
+//! is the depth of the node.
+//!
+//! And [`PostOrderPathsIter`](iter::PostOrderPathsIter), from
+//! [`.paths_post_order()`](FsTree::paths_post_order), yields `PathBuf` with children before their
+//! parents, useful for deleting files.
+//!
+//! And [`NodesIterMut`](iter::NodesIterMut), from [`.nodes_mut()`](FsTree::nodes_mut), yields
+//! `&mut FsTree` for regular files and symlinks, useful for rewriting them in bulk.
+//!
+//! And [`IntoNodesIter`](iter::IntoNodesIter), from [`.into_nodes()`](FsTree::into_nodes), yields
+//! owned `FsTree`s, useful for moving symlink targets out without cloning them.
 //!
 //! The yielded [`PathBuf`]s correspond to the full relative path to the current node, which is the
 //! result of concatenating the paths of every parent, and the current node.
@@ -45,15 +65,31 @@
 //! ```
 
 use std::{
-    collections::VecDeque,
+    cmp::Ordering,
+    collections::{btree_map, VecDeque},
+    fmt,
     path::{Path, PathBuf},
+    rc::Rc,
 };
 
-use crate::FsTree;
+use crate::{glob::Glob, FsTree};
 
-type NodeWithPathAndDepth<'a> = (&'a FsTree, usize, &'a Path);
+// The last element holds the parent path components, only used in breadth-first order
+type NodeWithPathAndDepth<'a> = (&'a FsTree, usize, &'a Path, Option<Rc<[&'a Path]>>);
 type NodesIterDeque<'a> = VecDeque<NodeWithPathAndDepth<'a>>;
 
+type Comparator<'a> = dyn Fn(&Path, &FsTree, &Path, &FsTree) -> Ordering + 'a;
+
+/// Comparator for sibling entries, set by the `sort_by` methods.
+#[derive(Clone)]
+struct SortBy<'a>(Rc<Comparator<'a>>);
+
+impl fmt::Debug for SortBy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SortBy(..)")
+    }
+}
+
 /// This is the underlying iterator implementation for the other iterators.
 ///
 /// It does not implement the `Iterator` trait, instead, it has its own `.next()` method, because
@@ -61,7 +97,7 @@ type NodesIterDeque<'a> = VecDeque<NodeWithPathAndDepth<'a>>;
 #[derive(Debug, Clone)]
 struct InnerIter<'a> {
     // Always pop from the front
-    // Push to front or back, if it's a directory or not, respectively, to yield in DFS-order
+    // Push children to the front to yield in DFS-order, or to the back for BFS-order
     file_deque: NodesIterDeque<'a>,
     // Accessed by the `depth` method, determined by the last yielded element
     current_depth: usize,
@@ -71,9 +107,13 @@ struct InnerIter<'a> {
     skip_symlinks: bool,
     min_depth: usize,
     max_depth: usize,
-
-    /// TODO: what is this
-    last_path: &'a Path,
+    breadth_first: bool,
+    // Order of siblings, `TrieMap` order if `None`
+    sort_by: Option<SortBy<'a>>,
+    // Only yield entries whose path matches, if set
+    glob: Option<Glob>,
+    // Path components of the last popped element, skipped or not, the root has none
+    path_components: Vec<&'a Path>,
 }
 
 impl<'a> InnerIter<'a> {
@@ -81,7 +121,7 @@ impl<'a> InnerIter<'a> {
         // Deque used for iterate in recursive structure
         let mut file_deque = VecDeque::new();
         // Starting deque from `start_file`, at depth 0, which can increase for each directory found
-        file_deque.push_back((start_file, 0, Path::new("")));
+        file_deque.push_back((start_file, 0, Path::new(""), None));
 
         Self {
             file_deque,
@@ -91,13 +131,40 @@ impl<'a> InnerIter<'a> {
             skip_symlinks: false,
             min_depth: usize::MIN,
             max_depth: usize::MAX,
-            last_path: Path::new(""),
+            breadth_first: false,
+            sort_by: None,
+            glob: None,
+            path_components: Vec::new(),
+        }
+    }
+
+    /// Push the children of a directory at `depth` to the deque, `children` must be in the order
+    /// they should be yielded.
+    fn push_children(
+        &mut self,
+        depth: usize,
+        children: impl DoubleEndedIterator<Item = (&'a Path, &'a FsTree)>,
+    ) {
+        if self.breadth_first {
+            // Shared by all children
+            let parent_components = Rc::<[&Path]>::from(self.path_components.as_slice());
+
+            for (path, child) in children {
+                let parent_components = Some(Rc::clone(&parent_components));
+                self.file_deque
+                    .push_back((child, depth + 1, path, parent_components));
+            }
+        } else {
+            // Reversed, to preserve order (push_front is different)
+            for (path, child) in children.rev() {
+                self.file_deque.push_front((child, depth + 1, path, None));
+            }
         }
     }
 
-    /// Let other iterators access the inner Path reference.
-    fn last_path(&self) -> &Path {
-        self.last_path
+    /// Let other iterators access the path components of the last yielded element.
+    fn path_components(&self) -> &[&'a Path] {
+        &self.path_components
     }
 
     fn depth(&self) -> usize {
@@ -110,16 +177,34 @@ impl<'a> Iterator for InnerIter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         // Pop last element, if any
-        let (file, depth, last_path) = self.file_deque.pop_front()?;
+        let (file, depth, path, parent_components) = self.file_deque.pop_front()?;
 
         // Update current_depth, for `.depth()` method
         self.current_depth = depth;
 
+        // Update the path, even if this element is skipped, its children might not be
+        if depth > 0 {
+            // In DFS-order, the parent components are the start of the last popped element's
+            match parent_components {
+                Some(parent_components) => {
+                    self.path_components.clear();
+                    self.path_components.extend_from_slice(&parent_components);
+                },
+                None => self.path_components.truncate(depth - 1),
+            }
+            self.path_components.push(path);
+        }
+
         // If directory, add children
         if let Some(children) = file.children() {
-            // Reversed, to preserve order (push_front is different)
-            for (path, child) in children.iter().rev() {
-                self.file_deque.push_front((child, depth + 1, path));
+            let children = children.iter().map(|(path, child)| (path.as_path(), child));
+
+            if let Some(SortBy(compare)) = self.sort_by.clone() {
+                let mut children: Vec<_> = children.collect();
+                children.sort_by(|(a_path, a), (b_path, b)| compare(a_path, a, b_path, b));
+                self.push_children(depth, children.into_iter());
+            } else {
+                self.push_children(depth, children);
             }
         }
 
@@ -129,13 +214,15 @@ impl<'a> Iterator for InnerIter<'a> {
             || self.skip_symlinks && file.is_symlink()
             || self.min_depth > depth
             || self.max_depth < depth
+            || self.glob.as_ref().is_some_and(|glob| {
+                let path: PathBuf = self.path_components.iter().collect();
+                !glob.matches(&path)
+            })
         {
             // Skipping and calling the next one, if any
             return self.next();
         }
 
-        self.last_path = last_path;
-
         Some(file)
     }
 }
@@ -183,6 +270,80 @@ macro_rules! impl_iter_methods {
             self.$($path_to_the_inner_iter)*.max_depth = max;
             self
         }
+
+        /// Filter out entries whose path (relative to the root) doesn't match the glob `pattern`.
+        ///
+        /// Directories are still traversed when they don't match, so `**/*.toml` yields files at
+        /// any depth. The root has an empty path, only matched by patterns like `**`.
+        ///
+        /// See [`FsTree::count_glob`] for the supported syntax.
+        ///
+        /// # Examples:
+        ///
+        /// ```
+        /// use std::path::PathBuf;
+        ///
+        /// use fs_tree::tree;
+        ///
+        /// let tree = tree! {
+        ///     "Cargo.toml"
+        ///     crates: {
+        ///         inner: {
+        ///             "Cargo.toml"
+        ///             "lib.rs"
+        ///         }
+        ///     }
+        /// };
+        ///
+        /// let paths: Vec<PathBuf> = tree.paths().glob("**/*.toml").collect();
+        ///
+        /// assert_eq!(paths, ["Cargo.toml", "crates/inner/Cargo.toml"].map(PathBuf::from));
+        /// ```
+        pub fn glob(mut self, pattern: &str) -> Self {
+            self.$($path_to_the_inner_iter)*.glob = Some(Glob::new(pattern));
+            self
+        }
+
+        /// Traverse in breadth-first order, yielding all entries of a depth before the next one.
+        ///
+        /// Siblings keep their order, this should be set before the first `.next()` call.
+        pub fn breadth_first(mut self, arg: bool) -> Self {
+            self.$($path_to_the_inner_iter)*.breadth_first = arg;
+            self
+        }
+
+        /// Order siblings with a comparator, called with the name and node of two siblings.
+        ///
+        /// By default, siblings are yielded in the `TrieMap` order (sorted by name). The sort is
+        /// stable, so siblings that compare equal keep that order.
+        ///
+        /// # Examples:
+        ///
+        /// ```
+        /// use std::path::PathBuf;
+        ///
+        /// use fs_tree::tree;
+        ///
+        /// let tree = tree! {
+        ///     a
+        ///     b: {}
+        /// };
+        ///
+        /// // Directories first
+        /// let paths: Vec<PathBuf> = tree
+        ///     .paths()
+        ///     .sort_by(|_, a, _, b| b.is_dir().cmp(&a.is_dir()))
+        ///     .collect();
+        ///
+        /// assert_eq!(paths, ["", "b", "a"].map(PathBuf::from));
+        /// ```
+        pub fn sort_by(
+            mut self,
+            compare: impl Fn(&Path, &FsTree, &Path, &FsTree) -> Ordering + 'a,
+        ) -> Self {
+            self.$($path_to_the_inner_iter)*.sort_by = Some(SortBy(Rc::new(compare)));
+            self
+        }
     };
 }
 
@@ -214,6 +375,38 @@ impl<'a> Iterator for NodesIter<'a> {
     }
 }
 
+/// Tree nodes iterator, with their names.
+///
+/// Yields `(&Path, &FsTree)`, the path is the node name (last component), the root has an empty
+/// name.
+///
+/// Created by `FsTree::nodes_with_names`.
+#[derive(Debug, Clone)]
+pub struct NamedNodesIter<'a> {
+    inner_iter: InnerIter<'a>,
+}
+
+impl<'a> NamedNodesIter<'a> {
+    pub(crate) fn new(root: &'a FsTree) -> Self {
+        Self {
+            inner_iter: InnerIter::new(root),
+        }
+    }
+
+    impl_iter_methods!(inner_iter);
+}
+
+impl<'a> Iterator for NamedNodesIter<'a> {
+    type Item = (&'a Path, &'a FsTree);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.inner_iter.next()?;
+        let name = self.inner_iter.path_components().last().copied();
+
+        Some((name.unwrap_or(Path::new("")), node))
+    }
+}
+
 /// Tree iterator.
 ///
 /// Yields `(&FsTree, PathBuf)`.
@@ -222,19 +415,36 @@ impl<'a> Iterator for NodesIter<'a> {
 #[derive(Debug, Clone)]
 pub struct Iter<'a> {
     inner_iter: InnerIter<'a>,
-    path_builder: PathBuf,
-    previous_depth: usize,
 }
 
 impl<'a> Iter<'a> {
     pub(crate) fn new(root: &'a FsTree) -> Self {
         Self {
             inner_iter: InnerIter::new(root),
-            path_builder: PathBuf::new(),
-            previous_depth: 0,
         }
     }
 
+    /// Adapts this iterator to also yield the depth of each node, see [`DepthIter`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    /// };
+    ///
+    /// for (depth, _node, path) in tree.iter().with_depth() {
+    ///     println!("{}{}", "  ".repeat(depth), path.display());
+    /// }
+    /// ```
+    pub fn with_depth(self) -> DepthIter<'a> {
+        DepthIter { iter: self }
+    }
+
     impl_iter_methods!(inner_iter);
 }
 
@@ -244,18 +454,34 @@ impl<'a> Iterator for Iter<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let node = self.inner_iter.next()?;
-        let new_depth = self.inner_iter.depth();
-        let last_path = self.inner_iter.last_path();
+        let path = self.inner_iter.path_components().iter().collect();
 
-        for _ in new_depth..=self.previous_depth {
-            self.path_builder.pop();
-        }
+        Some((node, path))
+    }
+}
 
-        self.path_builder.push(last_path);
+/// Tree iterator, with depths.
+///
+/// Yields `(usize, &FsTree, PathBuf)`, where the first element is the same value as
+/// [`Iter::depth`] would report for the node, `0` for the root.
+///
+/// Created by [`Iter::with_depth`].
+#[derive(Debug, Clone)]
+pub struct DepthIter<'a> {
+    iter: Iter<'a>,
+}
+
+impl<'a> DepthIter<'a> {
+    impl_iter_methods!(iter.inner_iter);
+}
 
-        self.previous_depth = new_depth;
+impl<'a> Iterator for DepthIter<'a> {
+    type Item = (usize, &'a FsTree, PathBuf);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, path) = self.iter.next()?;
 
-        Some((node, self.path_builder.clone()))
+        Some((self.iter.depth(), node, path))
     }
 }
 
@@ -284,11 +510,151 @@ impl Iterator for PathsIter<'_> {
     }
 }
 
+/// Iterator for each path inside of the recursive struct, in post-order (children first).
+///
+/// Created by `FsTree::paths_post_order`.
+#[derive(Debug, Clone)]
+pub struct PostOrderPathsIter<'a> {
+    // Each frame is a node path, and an iterator over its remaining children, if any
+    stack: Vec<(PathBuf, Option<btree_map::Iter<'a, PathBuf, FsTree>>)>,
+}
+
+impl<'a> PostOrderPathsIter<'a> {
+    pub(crate) fn new(root: &'a FsTree) -> Self {
+        Self {
+            stack: vec![(
+                PathBuf::new(),
+                root.children().map(|children| children.iter()),
+            )],
+        }
+    }
+}
+
+impl Iterator for PostOrderPathsIter<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (path, children) = self.stack.last_mut()?;
+
+            // Descend into the next child, or yield the node after all children were yielded
+            match children.as_mut().and_then(Iterator::next) {
+                Some((name, child)) => {
+                    let child_path = path.join(name);
+                    let grandchildren = child.children().map(|children| children.iter());
+                    self.stack.push((child_path, grandchildren));
+                },
+                None => return self.stack.pop().map(|(path, _)| path),
+            }
+        }
+    }
+}
+
+/// Mutable iterator over the leaf nodes (regular files and symlinks), in DFS order.
+///
+/// Directories are traversed but not yielded, because a mutable reference to a directory would
+/// alias the mutable references to its children.
+///
+/// Created by `FsTree::nodes_mut`.
+#[derive(Debug)]
+pub struct NodesIterMut<'a> {
+    // The root, before the first `.next()` call
+    root: Option<&'a mut FsTree>,
+    // The remaining children of each directory being traversed
+    stack: Vec<btree_map::ValuesMut<'a, PathBuf, FsTree>>,
+}
+
+impl<'a> NodesIterMut<'a> {
+    pub(crate) fn new(root: &'a mut FsTree) -> Self {
+        Self {
+            root: Some(root),
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for NodesIterMut<'a> {
+    type Item = &'a mut FsTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            match root {
+                FsTree::Directory(children) => self.stack.push(children.values_mut()),
+                leaf => return Some(leaf),
+            }
+        }
+
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(FsTree::Directory(children)) => self.stack.push(children.values_mut()),
+                Some(leaf) => return Some(leaf),
+                None => {
+                    self.stack.pop();
+                },
+            }
+        }
+    }
+}
+
+/// Consuming iterator over the nodes of a tree, in DFS order.
+///
+/// Yields owned `FsTree`s, the `TrieMap`s are drained as they are traversed, so directories are
+/// yielded empty, and their children are yielded right after them.
+///
+/// Created by `FsTree::into_nodes`.
+#[derive(Debug)]
+pub struct IntoNodesIter {
+    // The root, before the first `.next()` call
+    root: Option<FsTree>,
+    // The remaining children of each directory being traversed
+    stack: Vec<btree_map::IntoValues<PathBuf, FsTree>>,
+}
+
+impl IntoNodesIter {
+    pub(crate) fn new(root: FsTree) -> Self {
+        Self {
+            root: Some(root),
+            stack: Vec::new(),
+        }
+    }
+
+    fn drain(&mut self, node: FsTree) -> FsTree {
+        match node {
+            FsTree::Directory(children) => {
+                self.stack.push(children.into_values());
+                FsTree::new_dir()
+            },
+            leaf => leaf,
+        }
+    }
+}
+
+impl Iterator for IntoNodesIter {
+    type Item = FsTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            return Some(self.drain(root));
+        }
+
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(node) => return Some(self.drain(node)),
+                None => {
+                    self.stack.pop();
+                },
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::path::{Path, PathBuf};
+
     use pretty_assertions::assert_eq;
 
-    use crate::tree;
+    use crate::{tree, FsTree};
 
     #[test]
     #[rustfmt::skip]
@@ -396,4 +762,389 @@ mod tests {
         assert_eq!(it.next(), Some(refs[10])); // ".config/outerfile2"
         assert_eq!(it.next(), None);
     }
+
+    #[test]
+    fn test_with_depth() {
+        let tree = tree! {
+            ".config": {
+                i3: {
+                    file1
+                    file2
+                    dir: {
+                        innerfile1
+                        innerfile2
+                    }
+                    file3
+                }
+                outerfile1
+                outerfile2
+            }
+        };
+
+        let depths: Vec<(usize, PathBuf)> = tree
+            .iter()
+            .with_depth()
+            .map(|(depth, node, path)| {
+                assert_eq!(node, &tree[&path]);
+                (depth, path)
+            })
+            .collect();
+        let expected = [
+            (0, ""),
+            (1, ".config"),
+            (2, ".config/i3"),
+            (3, ".config/i3/dir"),
+            (4, ".config/i3/dir/innerfile1"),
+            (4, ".config/i3/dir/innerfile2"),
+            (3, ".config/i3/file1"),
+            (3, ".config/i3/file2"),
+            (3, ".config/i3/file3"),
+            (2, ".config/outerfile1"),
+            (2, ".config/outerfile2"),
+        ];
+        assert_eq!(depths, expected.map(|(depth, path)| (depth, path.into())));
+
+        // Filters apply before and after the adaptor
+        let depths: Vec<(usize, PathBuf)> = tree
+            .iter()
+            .breadth_first(true)
+            .with_depth()
+            .skip_regular_files(true)
+            .map(|(depth, _, path)| (depth, path))
+            .collect();
+        let expected = [
+            (0, ""),
+            (1, ".config"),
+            (2, ".config/i3"),
+            (3, ".config/i3/dir"),
+        ];
+        assert_eq!(depths, expected.map(|(depth, path)| (depth, path.into())));
+    }
+
+    #[test]
+    fn test_breadth_first() {
+        let tree = tree! {
+            ".config": {
+                i3: {
+                    file1
+                    file2
+                    dir: {
+                        innerfile1
+                        innerfile2
+                    }
+                    file3
+                }
+                outerfile1
+                outerfile2
+            }
+        };
+
+        let dfs: Vec<PathBuf> = tree.paths().collect();
+        let expected = [
+            "",
+            ".config",
+            ".config/i3",
+            ".config/i3/dir",
+            ".config/i3/dir/innerfile1",
+            ".config/i3/dir/innerfile2",
+            ".config/i3/file1",
+            ".config/i3/file2",
+            ".config/i3/file3",
+            ".config/outerfile1",
+            ".config/outerfile2",
+        ];
+        assert_eq!(dfs, expected.map(PathBuf::from));
+
+        let bfs: Vec<PathBuf> = tree.paths().breadth_first(true).collect();
+        let expected = [
+            "",
+            ".config",
+            ".config/i3",
+            ".config/outerfile1",
+            ".config/outerfile2",
+            ".config/i3/dir",
+            ".config/i3/file1",
+            ".config/i3/file2",
+            ".config/i3/file3",
+            ".config/i3/dir/innerfile1",
+            ".config/i3/dir/innerfile2",
+        ];
+        assert_eq!(bfs, expected.map(PathBuf::from));
+
+        // Depths are never decreasing
+        let mut it = tree.nodes().breadth_first(true);
+        let mut depths = vec![];
+        while it.next().is_some() {
+            depths.push(it.depth());
+        }
+        assert_eq!(depths, [0, 1, 2, 2, 2, 3, 3, 3, 3, 4, 4]);
+
+        // Filters still apply
+        let bfs: Vec<PathBuf> = tree
+            .paths()
+            .breadth_first(true)
+            .skip_dirs(true)
+            .max_depth(3)
+            .collect();
+        let expected = [
+            ".config/outerfile1",
+            ".config/outerfile2",
+            ".config/i3/file1",
+            ".config/i3/file2",
+            ".config/i3/file3",
+        ];
+        assert_eq!(bfs, expected.map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_sort_by_directories_first() {
+        let tree = tree! {
+            a
+            b: {
+                c
+                d: {}
+            }
+            e: {}
+            f -> target
+        };
+
+        let directories_first =
+            |_: &Path, a: &FsTree, _: &Path, b: &FsTree| b.is_dir().cmp(&a.is_dir());
+
+        let paths: Vec<PathBuf> = tree.paths().sort_by(directories_first).collect();
+        let expected = ["", "b", "b/d", "b/c", "e", "a", "f"];
+        assert_eq!(paths, expected.map(PathBuf::from));
+
+        // Composes with the other options
+        let paths: Vec<PathBuf> = tree
+            .paths()
+            .sort_by(directories_first)
+            .breadth_first(true)
+            .min_depth(1)
+            .skip_symlinks(true)
+            .collect();
+        let expected = ["b", "e", "a", "b/d", "b/c"];
+        assert_eq!(paths, expected.map(PathBuf::from));
+
+        // Reversed order
+        let names: Vec<&Path> = tree
+            .nodes_with_names()
+            .sort_by(|a, _, b, _| b.cmp(a))
+            .max_depth(1)
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["", "f", "e", "b", "a"].map(Path::new));
+    }
+
+    #[test]
+    fn test_glob() {
+        let tree = tree! {
+            "Cargo.toml"
+            src: {
+                "lib.rs"
+                "main.rs"
+                bin: {
+                    "a.rs"
+                    "b.rs"
+                    "c.toml"
+                }
+            }
+            tests: {
+                "test_a.rs"
+                "test_b.rs"
+            }
+        };
+
+        let glob = |pattern| tree.paths().glob(pattern).collect::<Vec<PathBuf>>();
+
+        assert_eq!(glob("*.toml"), ["Cargo.toml"].map(PathBuf::from));
+        assert_eq!(
+            glob("src/*"),
+            ["src/bin", "src/lib.rs", "src/main.rs"].map(PathBuf::from)
+        );
+        assert_eq!(
+            glob("**/*.toml"),
+            ["Cargo.toml", "src/bin/c.toml"].map(PathBuf::from)
+        );
+        assert_eq!(
+            glob("**/[a-b].rs"),
+            ["src/bin/a.rs", "src/bin/b.rs"].map(PathBuf::from)
+        );
+        assert_eq!(
+            glob("tests/test_[!a]*"),
+            ["tests/test_b.rs"].map(PathBuf::from)
+        );
+        assert_eq!(glob("**").len(), tree.len_all());
+
+        // Composes with the other filters, and keeps reporting the right depth
+        let mut it = tree.nodes().glob("**/*.rs").max_depth(2).skip_dirs(true);
+        let mut depths = vec![];
+        while it.next().is_some() {
+            depths.push(it.depth());
+        }
+        assert_eq!(depths, [2, 2, 2, 2]);
+
+        let paths: Vec<PathBuf> = tree.paths().glob("**/*.rs").breadth_first(true).collect();
+        let expected = [
+            "src/lib.rs",
+            "src/main.rs",
+            "tests/test_a.rs",
+            "tests/test_b.rs",
+            "src/bin/a.rs",
+            "src/bin/b.rs",
+        ];
+        assert_eq!(paths, expected.map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_nodes_with_names() {
+        let tree = tree! {
+            a: {
+                b
+                c -> target
+            }
+            d
+        };
+
+        let names: Vec<&str> = tree
+            .nodes_with_names()
+            .map(|(name, _)| name.to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["", "a", "b", "c", "d"]);
+
+        let nodes: Vec<_> = tree.nodes_with_names().map(|(_, node)| node).collect();
+        assert_eq!(nodes, tree.nodes().collect::<Vec<_>>());
+
+        let names: Vec<_> = tree
+            .nodes_with_names()
+            .skip_dirs(true)
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["b", "c", "d"].map(std::path::Path::new));
+    }
+
+    #[test]
+    fn test_paths_post_order() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                }
+                d
+                empty: {}
+            }
+            e
+        };
+
+        let paths: Vec<PathBuf> = tree.paths_post_order().collect();
+        let expected = ["a/b/c", "a/b", "a/d", "a/empty", "a", "e", ""].map(PathBuf::from);
+
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn test_paths_with_skipped_parents() {
+        let tree = tree! {
+            a: {
+                b: {
+                    c
+                }
+                d
+            }
+            e
+        };
+
+        let paths: Vec<PathBuf> = tree.paths().skip_dirs(true).collect();
+        assert_eq!(paths, ["a/b/c", "a/d", "e"].map(PathBuf::from));
+
+        let paths: Vec<PathBuf> = tree.paths().min_depth(2).collect();
+        assert_eq!(paths, ["a/b", "a/b/c", "a/d"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_nodes_mut() {
+        let mut tree = tree! {
+            a: {
+                b: {
+                    link -> target
+                }
+                file
+                empty: {}
+            }
+            other_link -> other_target
+        };
+
+        for node in tree.nodes_mut() {
+            if let Some(target) = node.target_mut() {
+                *target = PathBuf::from("new").join(&*target);
+            }
+        }
+
+        let expected = tree! {
+            a: {
+                b: {
+                    link -> "new/target"
+                }
+                file
+                empty: {}
+            }
+            other_link -> "new/other_target"
+        };
+        assert_eq!(tree, expected);
+
+        // Only leaves are yielded, in DFS order
+        let leaves: Vec<_> = tree.nodes_mut().map(|node| node.clone()).collect();
+        let expected: Vec<_> = tree
+            .nodes()
+            .filter(|node| !node.is_dir())
+            .cloned()
+            .collect();
+        assert_eq!(leaves, expected);
+
+        let mut root = FsTree::Regular;
+        assert_eq!(root.nodes_mut().count(), 1);
+    }
+
+    #[test]
+    fn test_into_nodes() {
+        let tree = tree! {
+            a: {
+                b: {
+                    link -> target
+                }
+                file
+                empty: {}
+            }
+            other_link -> other_target
+        };
+
+        // Same order as `nodes`, with directories drained
+        let expected: Vec<FsTree> = tree
+            .nodes()
+            .map(|node| {
+                match node {
+                    FsTree::Directory(_) => FsTree::new_dir(),
+                    leaf => leaf.clone(),
+                }
+            })
+            .collect();
+        assert_eq!(tree.clone().into_nodes().collect::<Vec<_>>(), expected);
+
+        // Owned targets, moved out of the tree
+        let targets: Vec<PathBuf> = tree
+            .into_nodes()
+            .filter_map(|node| {
+                match node {
+                    FsTree::Symlink(target) => Some(target),
+                    _ => None,
+                }
+            })
+            .collect();
+        assert_eq!(targets, ["target", "other_target"].map(PathBuf::from));
+
+        assert_eq!(
+            FsTree::Regular.into_nodes().collect::<Vec<_>>(),
+            [FsTree::Regular]
+        );
+    }
 }
@@ -29,6 +29,25 @@
 //! The root of the `FsTree` is **unnamed** (no filename/path), the "edges" to children are the
 //! relative paths.
 //!
+//! Those are the real [`FsTree`] and [`TrieMap`] types, so a directory is built from its
+//! `(PathBuf, FsTree)` children with the usual `BTreeMap` conversions:
+//!
+//! ```
+//! use std::path::PathBuf;
+//!
+//! use fs_tree::{tree, FsTree, TrieMap};
+//!
+//! let children: Vec<(PathBuf, FsTree)> = vec![
+//!     ("file".into(), FsTree::Regular),
+//!     ("dir".into(), FsTree::Directory(TrieMap::new())),
+//!     ("link".into(), FsTree::Symlink("file".into())),
+//! ];
+//!
+//! let root = FsTree::Directory(TrieMap::from_iter(children));
+//!
+//! assert_eq!(root, tree! { file dir: {} link -> file });
+//! ```
+//!
 //! # Pitfall warning:
 //!
 //! Like `std` functions, functions in this crate follow symlinks (and symlink chains), so you'll
@@ -41,15 +60,19 @@
 //! 2. Declare a `FsTree` literal. ([`tree!`])
 //! 3. Insert each node in an empty folder. ([`FsTree::new_dir`] + [`FsTree::insert`])
 //! 4. Parse from path text segments. ([`FsTree::from_path_text`])
+//! 5. Build it fluently, creating parent directories. ([`FsTree::builder`])
+//! 6. Read from a custom source of directory entries. ([`FsTree::read_with`])
+//! 7. Collect `(path, kind)` entries, from archives or other sources. ([`FsTree::from_entries`])
+//! 8. Read the entries of a tar archive, with the `tar` feature. (`FsTree::from_tar`)
 //!
 //! # What you can do with a [`FsTree`]:
 //!
 //! 1. Traverse, query, and modify it.
-//! 2. Merge with another tree. ([`FsTree::merge`])
+//! 2. Merge with another tree. ([`FsTree::merge`] and [`FsTree::try_merge`])
 //! 3. Write it to disk. ([`FsTree::write_at`])
 //! 4. Try loading a structural copy of it from a path. ([`FsTree::read_structure_at`])
-//! 5. (TODO) Compare with another `FsTree`, generating a DiffTree.
-//! 6. (TODO) Add entry API.
+//! 5. Compare with another `FsTree`, generating a [`DiffTree`]. ([`FsTree::diff`])
+//! 6. Insert or modify nodes in place, with the entry API. ([`FsTree::entry`])
 //!
 //! ## Iterators:
 //!
@@ -76,11 +99,42 @@
 pub mod iter;
 
 pub use self::{
-    error::{Error, Result},
-    fs_tree::{FsTree, TrieMap},
+    builder::FsTreeBuilder,
+    diff::{DiffTree, FsTreeDiff},
+    dir_source::{DirSource, StdDirSource},
+    display::PathsStringOptions,
+    entry::Entry,
+    error::{Error, InsertError, PathError, RenameError, Result},
+    fs_tree::{FsTree, TrieMap, WalkAction, WriteAction, WriteOptions},
+    merge::{MergeConflict, MergeOptions},
+    metadata::NodeMetadata,
+    node_kind::NodeKind,
+    read::{ReadOptions, TargetResolution},
+    rename::RenameOptions,
+    side_table::SideTable,
+    stats::{FsTreeStats, TreeStats},
 };
 
+mod builder;
+mod diff;
+mod dir_source;
+mod display;
+mod entry;
 mod error;
 mod fs_tree;
+mod gitignore;
+mod glob;
+mod hash;
 mod macros;
+mod merge;
+mod metadata;
+mod node_kind;
+mod read;
+mod rename;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod side_table;
+mod stats;
+#[cfg(feature = "tar")]
+mod tar_impl;
 pub(crate) mod utils;
@@ -7,8 +7,15 @@
 /// - `name` is a regular file.
 /// - `name: { ... }` is a directory.
 /// - `name -> name` is a symlink.
+/// - `name = "contents"` is a regular file, the contents are only kept by [`tree_with_contents!`],
+///   and are discarded here.
 /// - Commas are (unfortunately) not supported.
 /// - Use quotes (`"name"`) for spaces, dots, etc.
+/// - Use parentheses (`(expr)`) to interpolate any expression that implements `Into<PathBuf>`,
+///   both for names and symlink targets.
+///
+/// What comes after the name decides the node type, so there's no ambiguity between them. If a
+/// name is repeated in the same directory, the last entry wins.
 ///
 /// # Examples:
 ///
@@ -41,6 +48,29 @@
 ///
 /// assert_eq!(result, expected);
 /// ```
+///
+/// Interpolating variables:
+///
+/// ```
+/// use fs_tree::tree;
+///
+/// let name = String::from("config");
+/// let target = "/etc/config";
+///
+/// let result = tree! {
+///     (name): {
+///         (format!("{name}.toml"))
+///     }
+///     link -> (target)
+/// };
+///
+/// assert_eq!(result, tree! {
+///     config: {
+///         "config.toml"
+///     }
+///     link -> "/etc/config"
+/// });
+/// ```
 #[macro_export]
 macro_rules! tree {
     ($($all:tt)+) => {{
@@ -50,88 +80,159 @@ macro_rules! tree {
     }};
 }
 
+/// Macro for declaring a [`FsTree`](crate::FsTree) literal, along with the contents of its
+/// regular files.
+///
+/// Same syntax as [`tree!`], returns `(FsTree, BTreeMap<PathBuf, Vec<u8>>)`, where the map holds
+/// the contents of each file declared with `name = "contents"`, keyed by its path.
+///
+/// Contents can be anything that implements `Into<Vec<u8>>`, like string and byte string
+/// literals, or an expression inside of parentheses.
+///
+/// Files without contents aren't in the map, see [`FsTree::write_at_with`] for writing them.
+///
+/// [`FsTree::write_at_with`]: crate::FsTree::write_at_with
+///
+/// # Examples:
+///
+/// ```
+/// use std::path::Path;
+///
+/// use fs_tree::{tree, tree_with_contents};
+///
+/// let (tree, contents) = tree_with_contents! {
+///     "Cargo.toml" = "[package]"
+///     src: {
+///         "main.rs" = b"fn main() {}"
+///         "empty.rs"
+///     }
+/// };
+///
+/// assert_eq!(tree, tree! { "Cargo.toml" src: { "main.rs" "empty.rs" } });
+/// assert_eq!(contents.len(), 2);
+/// assert_eq!(contents[Path::new("src/main.rs")], b"fn main() {}");
+/// ```
+#[macro_export]
+macro_rules! tree_with_contents {
+    ($($all:tt)+) => {{
+        let mut trie = $crate::TrieMap::new();
+        #[allow(unused_mut)]
+        let mut contents = ::std::collections::BTreeMap::<
+            ::std::path::PathBuf,
+            ::std::vec::Vec<u8>,
+        >::new();
+        $crate::trees_with_contents_internal!(trie contents $($all)*);
+        ($crate::FsTree::Directory(trie), contents)
+    }};
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! trees_internal {
     // Base case
     ($parent_trie:ident) => {};
     // Directory
-    ($parent_trie:ident $path:ident : { $($inner:tt)* } $($rest:tt)*) => {
-        #[allow(unused_mut)]
-        let mut trie = $crate::TrieMap::new();
-        $crate::trees_internal!(trie $($inner)*);
-        $parent_trie.insert(
-            ::std::path::PathBuf::from(stringify!($path)),
-            $crate::FsTree::Directory(trie)
-        );
-        $crate::trees_internal!($parent_trie $($rest)*)
-    };
-    // Directory variation
-    ($parent_trie:ident $path:literal : { $($inner:tt)* } $($rest:tt)*) => {
+    ($parent_trie:ident $path:tt : { $($inner:tt)* } $($rest:tt)*) => {
         #[allow(unused_mut)]
         let mut trie = $crate::TrieMap::new();
         $crate::trees_internal!(trie $($inner)*);
         $parent_trie.insert(
-            ::std::path::PathBuf::from($path),
+            $crate::trees_internal!(@path $path),
             $crate::FsTree::Directory(trie)
         );
         $crate::trees_internal!($parent_trie $($rest)*)
     };
     // Symlink
-    ($parent_trie:ident $path:ident -> $target:ident $($rest:tt)*) => {
+    ($parent_trie:ident $path:tt -> $target:tt $($rest:tt)*) => {
         $parent_trie.insert(
-            ::std::path::PathBuf::from(stringify!($path)),
-            $crate::FsTree::Symlink(::std::path::PathBuf::from(stringify!($target)))
+            $crate::trees_internal!(@path $path),
+            $crate::FsTree::Symlink($crate::trees_internal!(@path $target))
         );
         $crate::trees_internal!($parent_trie $($rest)*)
     };
-    // Symlink variation
-    ($parent_trie:ident $path:literal -> $target:ident $($rest:tt)*) => {
-        $parent_trie.insert(
-            ::std::path::PathBuf::from($path),
-            $crate::FsTree::Symlink(::std::path::PathBuf::from(stringify!($target)))
-        );
-        $crate::trees_internal!($parent_trie $($rest)*)
+    // Regular file with contents, which are discarded
+    ($parent_trie:ident $path:tt = $data:tt $($rest:tt)*) => {
+        $crate::trees_internal!($parent_trie $path $($rest)*)
     };
-    // Symlink variation
-    ($parent_trie:ident $path:ident -> $target:literal $($rest:tt)*) => {
+    // Regular file
+    ($parent_trie:ident $path:tt $($rest:tt)*) => {
         $parent_trie.insert(
-            ::std::path::PathBuf::from(stringify!($path)),
-            $crate::FsTree::Symlink(::std::path::PathBuf::from($target))
+            $crate::trees_internal!(@path $path),
+            $crate::FsTree::Regular
         );
-        $crate::trees_internal!($parent_trie $($rest)*)
+        $crate::trees_internal!($parent_trie $($rest)*);
     };
-    // Symlink variation
-    ($parent_trie:ident $path:literal -> $target:literal $($rest:tt)*) => {
-        $parent_trie.insert(
-            ::std::path::PathBuf::from($path),
-            $crate::FsTree::Symlink(::std::path::PathBuf::from($target))
-        );
-        $crate::trees_internal!($parent_trie $($rest)*)
+    // Path from an identifier, a literal, or an expression inside of parentheses
+    (@path $path:ident) => {
+        ::std::path::PathBuf::from(stringify!($path))
     };
-    // Regular file
-    ($parent_trie:ident $path:ident $($rest:tt)*) => {
+    (@path $path:literal) => {
+        ::std::path::PathBuf::from($path)
+    };
+    (@path ($path:expr)) => {
+        ::std::path::PathBuf::from($path)
+    };
+    // Contents from a literal, or an expression inside of parentheses
+    (@contents $data:literal) => {
+        ::std::vec::Vec::<u8>::from($data)
+    };
+    (@contents ($data:expr)) => {
+        ::std::vec::Vec::<u8>::from($data)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trees_with_contents_internal {
+    // Base case
+    ($parent_trie:ident $contents:ident) => {};
+    // Directory, contents of the children are keyed relative to it, and then moved under its name
+    ($parent_trie:ident $contents:ident $path:tt : { $($inner:tt)* } $($rest:tt)*) => {
+        #[allow(unused_mut)]
+        let mut trie = $crate::TrieMap::new();
+        #[allow(unused_mut)]
+        let mut contents = ::std::collections::BTreeMap::<
+            ::std::path::PathBuf,
+            ::std::vec::Vec<u8>,
+        >::new();
+        $crate::trees_with_contents_internal!(trie contents $($inner)*);
+        let path = $crate::trees_internal!(@path $path);
+        $contents.extend(contents.into_iter().map(|(inner, data)| (path.join(inner), data)));
+        $parent_trie.insert(path, $crate::FsTree::Directory(trie));
+        $crate::trees_with_contents_internal!($parent_trie $contents $($rest)*)
+    };
+    // Symlink
+    ($parent_trie:ident $contents:ident $path:tt -> $target:tt $($rest:tt)*) => {
         $parent_trie.insert(
-            ::std::path::PathBuf::from(stringify!($path)),
-            $crate::FsTree::Regular
+            $crate::trees_internal!(@path $path),
+            $crate::FsTree::Symlink($crate::trees_internal!(@path $target))
         );
-        $crate::trees_internal!($parent_trie $($rest)*);
+        $crate::trees_with_contents_internal!($parent_trie $contents $($rest)*)
+    };
+    // Regular file with contents
+    ($parent_trie:ident $contents:ident $path:tt = $data:tt $($rest:tt)*) => {
+        let path = $crate::trees_internal!(@path $path);
+        $contents.insert(path.clone(), $crate::trees_internal!(@contents $data));
+        $parent_trie.insert(path, $crate::FsTree::Regular);
+        $crate::trees_with_contents_internal!($parent_trie $contents $($rest)*)
     };
     // Regular file
-    ($parent_trie:ident $path:literal $($rest:tt)*) => {
+    ($parent_trie:ident $contents:ident $path:tt $($rest:tt)*) => {
         $parent_trie.insert(
-            ::std::path::PathBuf::from($path),
+            $crate::trees_internal!(@path $path),
             $crate::FsTree::Regular
         );
-        $crate::trees_internal!($parent_trie $($rest)*);
+        $crate::trees_with_contents_internal!($parent_trie $contents $($rest)*);
     };
 }
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::BTreeMap, fs, path::PathBuf};
+
     use pretty_assertions::assert_eq;
 
-    use crate::{FsTree, TrieMap};
+    use crate::{utils::testdir, FsTree, TrieMap};
 
     #[test]
     fn test_macro_compiles_with_literals_and_idents() {
@@ -267,4 +368,103 @@ mod tests {
 
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_tree_macro_interpolation() {
+        let name = "config";
+        let owned = String::from("owned");
+
+        let result = tree! {
+            (name): {
+                file
+                (owned.clone()) -> (name)
+            }
+            link -> (name)
+            (owned): {}
+        };
+
+        let expected = tree! {
+            config: {
+                file
+                owned -> config
+            }
+            link -> config
+            owned: {}
+        };
+
+        assert_eq!(result, expected);
+
+        // Children are evaluated before the name of their directory, so it can be moved
+        let dir = String::from("dir");
+        let result = tree! {
+            (dir): {
+                (format!("{dir}.toml"))
+            }
+        };
+        assert_eq!(result, tree! { dir: { "dir.toml" } });
+
+        let dir = String::from("dir");
+        let (result, contents) = tree_with_contents! {
+            (dir): {
+                (format!("{dir}.toml")) = (dir.clone())
+            }
+        };
+        assert_eq!(result, tree! { dir: { "dir.toml" } });
+        assert_eq!(contents[&PathBuf::from("dir/dir.toml")], b"dir");
+    }
+
+    #[test]
+    fn test_tree_with_contents() {
+        let data = vec![0, 1, 2];
+
+        let (tree, contents) = tree_with_contents! {
+            "config.toml" = "key = 1"
+            dir: {
+                inner: {
+                    "data.bin" = (data.clone())
+                }
+                "bytes.txt" = b"bytes"
+                empty
+            }
+            link -> "config.toml"
+        };
+
+        let expected = tree! {
+            "config.toml" = "ignored"
+            dir: {
+                inner: {
+                    "data.bin"
+                }
+                "bytes.txt"
+                empty
+            }
+            link -> "config.toml"
+        };
+        assert_eq!(tree, expected);
+
+        let expected = BTreeMap::from([
+            (PathBuf::from("config.toml"), b"key = 1".to_vec()),
+            (PathBuf::from("dir/bytes.txt"), b"bytes".to_vec()),
+            (PathBuf::from("dir/inner/data.bin"), data),
+        ]);
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn test_tree_with_contents_writes_files() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let (tree, contents) = tree_with_contents! {
+            dir: {
+                file = "hello"
+            }
+            empty
+        };
+
+        tree.write_at_with(test_dir, |path| contents.get(path).cloned())
+            .unwrap();
+
+        assert_eq!(fs::read(test_dir.join("dir/file")).unwrap(), b"hello");
+        assert_eq!(fs::read(test_dir.join("empty")).unwrap(), b"");
+    }
 }
@@ -0,0 +1,958 @@
+//! Conflict-aware merging for [`FsTree`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error,
+    ffi::OsString,
+    fmt, mem,
+    path::{Component, Path, PathBuf},
+};
+
+use file_type_enum::FileType;
+
+use crate::{utils, FsTree, TrieMap};
+
+/// A conflict found while merging two trees with [`FsTree::try_merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// Both trees have a node at `path`, but the file types differ.
+    TypeConflict {
+        /// Path of the conflicting nodes.
+        path: PathBuf,
+        /// File type of the node in the left tree (`self`).
+        left: FileType,
+        /// File type of the node in the right tree (`other`).
+        right: FileType,
+    },
+    /// Both trees have a symlink at `path`, but they point to different targets.
+    SymlinkTargetConflict {
+        /// Path of the conflicting symlinks.
+        path: PathBuf,
+        /// Target of the symlink in the left tree (`self`).
+        left_target: PathBuf,
+        /// Target of the symlink in the right tree (`other`).
+        right_target: PathBuf,
+    },
+}
+
+/// Options for [`FsTree::try_merge_with`].
+///
+/// The default options are the ones used by [`FsTree::try_merge`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeOptions {
+    empty_dir_yields: bool,
+    follow_symlinks_on_merge: bool,
+}
+
+impl MergeOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat empty directories as absent when they'd conflict with a file.
+    ///
+    /// When one side has an empty directory and the other side has a regular file or symlink at
+    /// the same path, the file is kept instead of reporting a conflict. If both sides are
+    /// directories, they're merged as usual.
+    pub fn empty_dir_yields(mut self, arg: bool) -> Self {
+        self.empty_dir_yields = arg;
+        self
+    }
+
+    /// Merge a directory into the target of a symlink, when they'd conflict.
+    ///
+    /// When one side has a directory and the other side has a symlink at the same path, the
+    /// symlink is kept, and the directory contents are merged into the directory it points to,
+    /// like writing through the symlink would, following these rules:
+    ///
+    /// 1. Relative targets are resolved (lexically) from the symlink's parent directory.
+    /// 2. Absolute targets, and targets that escape the root (with `..`), are out of the tree, so
+    ///    they still conflict.
+    /// 3. The target must be a directory in the merged tree, reached without going through other
+    ///    symlinks, otherwise, it still conflicts.
+    ///
+    /// Only used by [`FsTree::try_merge_with`], [`FsTree::merge_renaming_conflicts`] renames the
+    /// node instead.
+    pub fn follow_symlinks_on_merge(mut self, arg: bool) -> Self {
+        self.follow_symlinks_on_merge = arg;
+        self
+    }
+}
+
+impl MergeConflict {
+    /// The path where the conflict happened.
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            Self::TypeConflict { path, .. } | Self::SymlinkTargetConflict { path, .. } => path,
+        }
+    }
+}
+
+impl error::Error for MergeConflict {}
+
+impl fmt::Display for MergeConflict {
+    /// Describes the conflict in a single line, meant to be shown to users.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TypeConflict { path, left, right } => {
+                write!(
+                    f,
+                    "conflicting file types at {}: {left} and {right}",
+                    path.display()
+                )
+            },
+            Self::SymlinkTargetConflict {
+                path,
+                left_target,
+                right_target,
+            } => {
+                write!(
+                    f,
+                    "conflicting symlink targets at {}: {} and {}",
+                    path.display(),
+                    left_target.display(),
+                    right_target.display()
+                )
+            },
+        }
+    }
+}
+
+impl FsTree {
+    /// Merge two trees, reporting every conflict found.
+    ///
+    /// Nodes at the same path are merged if both are directories (recursively), both are regular
+    /// files, or both are symlinks with the same target, everything else is a conflict.
+    ///
+    /// Unlike [`FsTree::merge`], conflicts aren't silently resolved by keeping `self`'s side.
+    ///
+    /// # Errors:
+    ///
+    /// - Returns all [`MergeConflict`]s found, in DFS order.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, MergeConflict};
+    ///
+    /// let left = tree! {
+    ///     link -> a
+    /// };
+    /// let right = tree! {
+    ///     link -> b
+    /// };
+    ///
+    /// let conflicts = left.try_merge(right).unwrap_err();
+    ///
+    /// assert_eq!(conflicts, [MergeConflict::SymlinkTargetConflict {
+    ///     path: "link".into(),
+    ///     left_target: "a".into(),
+    ///     right_target: "b".into(),
+    /// }]);
+    /// ```
+    pub fn try_merge(self, other: Self) -> Result<Self, Vec<MergeConflict>> {
+        self.try_merge_with(other, MergeOptions::default())
+    }
+
+    /// Merge two trees with the given [`MergeOptions`], reporting every conflict found.
+    ///
+    /// See [`FsTree::try_merge`].
+    ///
+    /// # Errors:
+    ///
+    /// - Returns all [`MergeConflict`]s found, in DFS order, except for conflicts found while
+    ///   merging directories into symlink targets (see
+    ///   [`MergeOptions::follow_symlinks_on_merge`]), which are appended after the rest.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, MergeOptions};
+    ///
+    /// let left = tree! {
+    ///     config: {}
+    /// };
+    /// let right = tree! {
+    ///     config
+    /// };
+    ///
+    /// let options = MergeOptions::new().empty_dir_yields(true);
+    /// let merged = left.try_merge_with(right, options).unwrap();
+    ///
+    /// assert!(merged["config"].is_regular());
+    /// ```
+    pub fn try_merge_with(
+        self,
+        other: Self,
+        options: MergeOptions,
+    ) -> Result<Self, Vec<MergeConflict>> {
+        let mut conflicts = vec![];
+        let mut deferred = vec![];
+        let mut merged = self.__try_merge(
+            other,
+            options,
+            &mut PathBuf::new(),
+            &mut conflicts,
+            &mut deferred,
+        );
+
+        // Directories that met symlinks are merged into their targets, this might defer more
+        // merges, but it always terminates, as deferred directories get smaller each time
+        while let Some(DeferredMerge {
+            mut target,
+            dir,
+            conflict,
+        }) = deferred.pop()
+        {
+            match merged.get_mut(&target) {
+                Some(node @ Self::Directory(_)) => {
+                    let node_before = mem::replace(node, Self::new_dir());
+                    *node = node_before.__try_merge(
+                        dir,
+                        options,
+                        &mut target,
+                        &mut conflicts,
+                        &mut deferred,
+                    );
+                },
+                _ => conflicts.push(conflict),
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(merged)
+        } else {
+            Err(conflicts)
+        }
+    }
+
+    fn __try_merge(
+        self,
+        other: Self,
+        options: MergeOptions,
+        path: &mut PathBuf,
+        conflicts: &mut Vec<MergeConflict>,
+        deferred: &mut Vec<DeferredMerge>,
+    ) -> Self {
+        match (self, other) {
+            (Self::Directory(mut left_children), Self::Directory(right_children)) => {
+                for (name, right_node) in right_children {
+                    let node = if let Some(left_node) = left_children.remove(&name) {
+                        path.push(&name);
+                        let node =
+                            left_node.__try_merge(right_node, options, path, conflicts, deferred);
+                        path.pop();
+                        node
+                    } else {
+                        right_node
+                    };
+
+                    left_children.insert(name, node);
+                }
+
+                Self::Directory(left_children)
+            },
+            (Self::Regular, Self::Regular) => Self::Regular,
+            (Self::Symlink(left_target), Self::Symlink(right_target)) => {
+                if left_target != right_target {
+                    conflicts.push(MergeConflict::SymlinkTargetConflict {
+                        path: path.clone(),
+                        left_target: left_target.clone(),
+                        right_target,
+                    });
+                }
+
+                Self::Symlink(left_target)
+            },
+            (Self::Directory(children), right)
+                if options.empty_dir_yields && children.is_empty() =>
+            {
+                right
+            },
+            (left, Self::Directory(children))
+                if options.empty_dir_yields && children.is_empty() =>
+            {
+                left
+            },
+            (Self::Symlink(target), right @ Self::Directory(_))
+                if options.follow_symlinks_on_merge =>
+            {
+                let conflict = MergeConflict::TypeConflict {
+                    path: path.clone(),
+                    left: FileType::Symlink,
+                    right: FileType::Directory,
+                };
+                defer_merge(path, &target, right, conflict, conflicts, deferred);
+
+                Self::Symlink(target)
+            },
+            (left @ Self::Directory(_), Self::Symlink(target))
+                if options.follow_symlinks_on_merge =>
+            {
+                let conflict = MergeConflict::TypeConflict {
+                    path: path.clone(),
+                    left: FileType::Directory,
+                    right: FileType::Symlink,
+                };
+                defer_merge(path, &target, left, conflict, conflicts, deferred);
+
+                Self::Symlink(target)
+            },
+            (left, right) => {
+                conflicts.push(MergeConflict::TypeConflict {
+                    path: path.clone(),
+                    left: left.file_type(),
+                    right: right.file_type(),
+                });
+
+                left
+            },
+        }
+    }
+
+    /// Merge two trees, keeping both sides of each conflict by renaming `other`'s node.
+    ///
+    /// When two nodes conflict (see [`FsTree::try_merge`] and [`MergeOptions`]), `self`'s node is
+    /// kept, and `other`'s is inserted next to it, with a numeric suffix added to its name to make
+    /// it unique, e.g. `link` becomes `link.1` (or `link.2`, if `link.1` is taken, and so on).
+    ///
+    /// Returns the merged tree and the renames performed, mapping the original path of each
+    /// renamed node to its new path.
+    ///
+    /// If the roots themselves conflict, there's nothing to rename, so `self` is kept.
+    ///
+    /// [`MergeOptions::follow_symlinks_on_merge`] is ignored, a directory and a symlink conflict.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use std::{collections::BTreeMap, path::PathBuf};
+    ///
+    /// use fs_tree::{tree, MergeOptions};
+    ///
+    /// let left = tree! {
+    ///     link -> a
+    /// };
+    /// let right = tree! {
+    ///     link -> b
+    /// };
+    ///
+    /// let (merged, renames) = left.merge_renaming_conflicts(right, MergeOptions::new());
+    ///
+    /// let expected = tree! {
+    ///     link -> a
+    ///     "link.1" -> b
+    /// };
+    ///
+    /// assert_eq!(merged, expected);
+    /// assert_eq!(renames, BTreeMap::from([("link".into(), "link.1".into())]));
+    /// ```
+    pub fn merge_renaming_conflicts(
+        self,
+        other: Self,
+        options: MergeOptions,
+    ) -> (Self, BTreeMap<PathBuf, PathBuf>) {
+        let mut renames = BTreeMap::new();
+        let merged =
+            self.__merge_renaming_conflicts(other, options, &mut PathBuf::new(), &mut renames);
+
+        (merged, renames)
+    }
+
+    fn __merge_renaming_conflicts(
+        self,
+        other: Self,
+        options: MergeOptions,
+        path: &mut PathBuf,
+        renames: &mut BTreeMap<PathBuf, PathBuf>,
+    ) -> Self {
+        let (mut left_children, right_children) = match (self, other) {
+            (Self::Directory(left_children), Self::Directory(right_children)) => {
+                (left_children, right_children)
+            },
+            // Not two directories, `self` is kept, unless it's an empty directory that yields
+            (left, right) => {
+                return if options.empty_dir_yields && left.is_empty() {
+                    right
+                } else {
+                    left
+                };
+            },
+        };
+
+        let right_names: BTreeSet<PathBuf> = right_children.keys().cloned().collect();
+
+        for (name, right_node) in right_children {
+            path.push(&name);
+
+            match left_children.remove(&name) {
+                None => {
+                    left_children.insert(name, right_node);
+                },
+                Some(left_node) if left_node.merges_cleanly_with(&right_node, options) => {
+                    let node =
+                        left_node.__merge_renaming_conflicts(right_node, options, path, renames);
+                    left_children.insert(name, node);
+                },
+                Some(left_node) => {
+                    let new_name = unique_name(&name, |candidate| {
+                        left_children.contains_key(candidate) || right_names.contains(candidate)
+                    });
+
+                    renames.insert(path.clone(), path.with_file_name(&new_name));
+                    left_children.insert(name, left_node);
+                    left_children.insert(new_name, right_node);
+                },
+            }
+
+            path.pop();
+        }
+
+        Self::Directory(left_children)
+    }
+
+    /// Checks if these two nodes can be merged without a conflict at this level.
+    fn merges_cleanly_with(&self, other: &Self, options: MergeOptions) -> bool {
+        match (self, other) {
+            (Self::Directory(_), Self::Directory(_)) | (Self::Regular, Self::Regular) => true,
+            (Self::Symlink(left_target), Self::Symlink(right_target)) => {
+                left_target == right_target
+            },
+            (Self::Directory(children), _) | (_, Self::Directory(children)) => {
+                options.empty_dir_yields && children.is_empty()
+            },
+            _ => false,
+        }
+    }
+
+    /// Three-way merge of `ours` and `theirs`, two trees derived from a common `base`.
+    ///
+    /// For each path:
+    /// - If only one side changed it (compared to `base`), that change is taken.
+    /// - If both sides changed it in the same way, that change is taken.
+    /// - If both sides changed a directory, its children are merged recursively.
+    /// - Otherwise, it's a conflict, the path is reported and `ours` is kept.
+    ///
+    /// A change can be an addition, a removal, or a modification.
+    ///
+    /// Returns the merged tree and the conflicting paths, in DFS order.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTree};
+    ///
+    /// let base = tree! {
+    ///     file
+    ///     link -> target
+    /// };
+    /// let ours = tree! {
+    ///     link -> target
+    ///     new_file
+    /// };
+    /// let theirs = tree! {
+    ///     file
+    ///     link -> other_target
+    /// };
+    ///
+    /// let (merged, conflicts) = FsTree::three_way_merge(&base, &ours, &theirs);
+    ///
+    /// let expected = tree! {
+    ///     link -> other_target
+    ///     new_file
+    /// };
+    ///
+    /// assert_eq!(merged, expected);
+    /// assert!(conflicts.is_empty());
+    /// ```
+    pub fn three_way_merge(base: &Self, ours: &Self, theirs: &Self) -> (Self, Vec<PathBuf>) {
+        let mut conflicts = vec![];
+
+        let merged = Self::__three_way_merge(
+            Some(base),
+            Some(ours),
+            Some(theirs),
+            &mut PathBuf::new(),
+            &mut conflicts,
+        );
+
+        // With all three roots present, the merged root is never removed
+        let merged = merged.expect("merged root is always present");
+
+        (merged, conflicts)
+    }
+
+    fn __three_way_merge(
+        base: Option<&Self>,
+        ours: Option<&Self>,
+        theirs: Option<&Self>,
+        path: &mut PathBuf,
+        conflicts: &mut Vec<PathBuf>,
+    ) -> Option<Self> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+
+        // Both sides changed
+        match (ours, theirs) {
+            (Some(Self::Directory(our_children)), Some(Self::Directory(their_children))) => {
+                let base_children = base.and_then(Self::children);
+
+                let names: BTreeSet<&PathBuf> = our_children
+                    .keys()
+                    .chain(their_children.keys())
+                    .chain(base_children.into_iter().flat_map(TrieMap::keys))
+                    .collect();
+
+                let mut children = TrieMap::new();
+
+                for name in names {
+                    path.push(name);
+                    let child = Self::__three_way_merge(
+                        base_children.and_then(|children| children.get(name)),
+                        our_children.get(name),
+                        their_children.get(name),
+                        path,
+                        conflicts,
+                    );
+                    path.pop();
+
+                    if let Some(child) = child {
+                        children.insert(name.clone(), child);
+                    }
+                }
+
+                Some(Self::Directory(children))
+            },
+            _ => {
+                conflicts.push(path.clone());
+                ours.cloned()
+            },
+        }
+    }
+}
+
+/// A directory to be merged into the target of a symlink, see
+/// [`MergeOptions::follow_symlinks_on_merge`].
+struct DeferredMerge {
+    /// Path of the symlink target, relative to the root.
+    target: PathBuf,
+    dir: FsTree,
+    /// Reported if the target isn't a directory in the merged tree.
+    conflict: MergeConflict,
+}
+
+/// Defers merging `dir` into the target of the symlink at `path`, or reports `conflict` if the
+/// target is out of the tree.
+fn defer_merge(
+    path: &Path,
+    target: &Path,
+    dir: FsTree,
+    conflict: MergeConflict,
+    conflicts: &mut Vec<MergeConflict>,
+    deferred: &mut Vec<DeferredMerge>,
+) {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let resolved = utils::normalize_lexically(&parent.join(target));
+
+    let is_in_tree = !target.is_absolute()
+        && resolved
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if is_in_tree {
+        // Skip the `.` that `normalize_lexically` returns for the root
+        let target = resolved
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect();
+        deferred.push(DeferredMerge {
+            target,
+            dir,
+            conflict,
+        });
+    } else {
+        conflicts.push(conflict);
+    }
+}
+
+/// Appends the first numeric suffix (`.1`, `.2`, ...) that makes `name` not taken.
+fn unique_name(name: &Path, is_taken: impl Fn(&Path) -> bool) -> PathBuf {
+    for suffix in 1.. {
+        let mut candidate = OsString::from(name);
+        candidate.push(format!(".{suffix}"));
+
+        let candidate = PathBuf::from(candidate);
+        if !is_taken(&candidate) {
+            return candidate;
+        }
+    }
+
+    unreachable!("ran out of suffixes")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tree;
+
+    #[test]
+    fn test_try_merge_without_conflicts() {
+        let left = tree! {
+            file
+            link -> target
+            dir: {
+                a
+            }
+        };
+        let right = tree! {
+            file
+            link -> target
+            dir: {
+                b
+            }
+        };
+
+        let expected = tree! {
+            file
+            link -> target
+            dir: {
+                a
+                b
+            }
+        };
+
+        assert_eq!(left.try_merge(right), Ok(expected));
+    }
+
+    #[test]
+    fn test_try_merge_reports_symlink_target_conflicts_separately() {
+        let left = tree! {
+            dir: {
+                link -> a
+                file
+            }
+        };
+        let right = tree! {
+            dir: {
+                link -> b
+                file: {}
+            }
+        };
+
+        let expected = vec![
+            MergeConflict::TypeConflict {
+                path: "dir/file".into(),
+                left: FileType::Regular,
+                right: FileType::Directory,
+            },
+            MergeConflict::SymlinkTargetConflict {
+                path: "dir/link".into(),
+                left_target: "a".into(),
+                right_target: "b".into(),
+            },
+        ];
+
+        assert_eq!(left.try_merge(right), Err(expected));
+    }
+
+    #[test]
+    fn test_merge_conflict_display() {
+        let left = tree! { dir: { file link -> a } };
+        let right = tree! { dir: { file: {} link -> b } };
+
+        let conflicts = left.try_merge(right).unwrap_err();
+        let messages: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+
+        let expected_type_conflict = format!(
+            "conflicting file types at dir/file: {} and {}",
+            FileType::Regular,
+            FileType::Directory
+        );
+        assert_eq!(
+            messages,
+            [
+                expected_type_conflict,
+                "conflicting symlink targets at dir/link: a and b".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_merge_with_empty_dir_yields() {
+        let left = tree! {
+            a: {}
+            b
+            c: {}
+        };
+        let right = tree! {
+            a
+            b: {}
+            c: {}
+        };
+
+        let expected = tree! {
+            a
+            b
+            c: {}
+        };
+
+        let options = MergeOptions::new().empty_dir_yields(true);
+        assert_eq!(
+            left.clone().try_merge_with(right.clone(), options),
+            Ok(expected)
+        );
+        assert_eq!(left.try_merge(right).unwrap_err().len(), 2);
+    }
+
+    #[test]
+    fn test_try_merge_with_empty_dir_yields_non_empty_dir_conflicts() {
+        let left = tree! {
+            dir: {
+                file
+            }
+        };
+        let right = tree! {
+            dir
+        };
+
+        let options = MergeOptions::new().empty_dir_yields(true);
+        let expected = vec![MergeConflict::TypeConflict {
+            path: "dir".into(),
+            left: FileType::Directory,
+            right: FileType::Regular,
+        }];
+
+        assert_eq!(left.try_merge_with(right, options), Err(expected));
+    }
+
+    #[test]
+    fn test_try_merge_with_follow_symlinks() {
+        let left = tree! {
+            dotfiles: {
+                config: {
+                    existing
+                }
+            }
+            ".config" -> "dotfiles/config"
+            nested: {
+                up -> ".."
+            }
+        };
+        let right = tree! {
+            ".config": {
+                new
+                existing
+            }
+            nested: {
+                up: {
+                    root_file
+                }
+            }
+        };
+
+        let options = MergeOptions::new().follow_symlinks_on_merge(true);
+        let merged = left.clone().try_merge_with(right.clone(), options).unwrap();
+
+        let expected = tree! {
+            dotfiles: {
+                config: {
+                    existing
+                    new
+                }
+            }
+            ".config" -> "dotfiles/config"
+            nested: {
+                up -> ".."
+            }
+            root_file
+        };
+        assert_eq!(merged, expected);
+
+        // Works the same with the sides swapped
+        assert_eq!(
+            right.try_merge_with(left.clone(), options).unwrap(),
+            expected
+        );
+
+        // Without the option, it's a conflict
+        assert_eq!(
+            left.try_merge(tree! { ".config": {} }).unwrap_err().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_try_merge_with_follow_symlinks_unresolvable() {
+        let left = tree! {
+            absolute -> "/etc"
+            escaping -> "../outside"
+            missing -> "nowhere"
+            to_file -> file
+            file
+        };
+        let right = tree! {
+            absolute: {}
+            escaping: {}
+            missing: {}
+            to_file: {}
+        };
+
+        let options = MergeOptions::new().follow_symlinks_on_merge(true);
+        let conflicts = left.try_merge_with(right, options).unwrap_err();
+
+        let mut paths: Vec<&str> = conflicts
+            .iter()
+            .map(|conflict| conflict.path().to_str().unwrap())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["absolute", "escaping", "missing", "to_file"]);
+        assert!(conflicts.iter().all(|conflict| {
+            matches!(
+                conflict,
+                MergeConflict::TypeConflict {
+                    left: FileType::Symlink,
+                    right: FileType::Directory,
+                    ..
+                }
+            )
+        }));
+    }
+
+    #[test]
+    fn test_merge_renaming_conflicts() {
+        let left = tree! {
+            dir: {
+                link -> a
+                file
+                same_link -> a
+            }
+            conflict: {
+                inner
+            }
+        };
+        let right = tree! {
+            dir: {
+                link -> b
+                "link.1"
+                file -> c
+                same_link -> a
+            }
+            conflict
+        };
+
+        let (merged, renames) = left.merge_renaming_conflicts(right, MergeOptions::new());
+
+        let expected = tree! {
+            dir: {
+                link -> a
+                "link.1"
+                "link.2" -> b
+                file
+                "file.1" -> c
+                same_link -> a
+            }
+            conflict: {
+                inner
+            }
+            "conflict.1"
+        };
+        let expected_renames = BTreeMap::from([
+            ("conflict".into(), "conflict.1".into()),
+            ("dir/file".into(), "dir/file.1".into()),
+            ("dir/link".into(), "dir/link.2".into()),
+        ]);
+
+        assert_eq!(merged, expected);
+        assert_eq!(renames, expected_renames);
+
+        // Conflicting roots keep `self`, even when following symlinks
+        let options = MergeOptions::new().follow_symlinks_on_merge(true);
+        let dir = tree! { file };
+        let (merged, renames) = dir
+            .clone()
+            .merge_renaming_conflicts(FsTree::Symlink("dir".into()), options);
+        assert_eq!(merged, dir);
+        assert!(renames.is_empty());
+
+        let (merged, _) = FsTree::Symlink("dir".into()).merge_renaming_conflicts(dir, options);
+        assert_eq!(merged, FsTree::Symlink("dir".into()));
+    }
+
+    #[test]
+    fn test_three_way_merge() {
+        let base = tree! {
+            removed_by_ours
+            removed_by_theirs
+            changed_by_both -> base
+            dir: {
+                kept
+            }
+            conflict: {
+                file
+            }
+        };
+        let ours = tree! {
+            removed_by_theirs
+            changed_by_both -> same
+            dir: {
+                kept
+                added_by_ours
+            }
+            conflict -> ours
+            added_by_both
+        };
+        let theirs = tree! {
+            removed_by_ours
+            changed_by_both -> same
+            dir: {
+                kept
+                added_by_theirs
+            }
+            conflict
+            added_by_both
+        };
+
+        let (merged, conflicts) = FsTree::three_way_merge(&base, &ours, &theirs);
+
+        let expected = tree! {
+            changed_by_both -> same
+            dir: {
+                kept
+                added_by_ours
+                added_by_theirs
+            }
+            conflict -> ours
+            added_by_both
+        };
+
+        assert_eq!(merged, expected);
+        assert_eq!(conflicts, [PathBuf::from("conflict")]);
+    }
+
+    #[test]
+    fn test_three_way_merge_removal_against_change_conflicts() {
+        let base = tree! {
+            dir: {
+                file
+            }
+        };
+        let ours = FsTree::new_dir();
+        let theirs = tree! {
+            dir: {
+                file: {}
+            }
+        };
+
+        let (merged, conflicts) = FsTree::three_way_merge(&base, &ours, &theirs);
+
+        assert_eq!(merged, ours);
+        assert_eq!(conflicts, [PathBuf::from("dir")]);
+    }
+}
@@ -0,0 +1,38 @@
+//! Per-node metadata, captured by [`FsTree::read_at_with_metadata`].
+//!
+//! [`FsTree::read_at_with_metadata`]: crate::FsTree::read_at_with_metadata
+
+use std::{fs::Metadata, time::SystemTime};
+
+/// The metadata of a node, captured from its [`std::fs::Metadata`] while reading.
+///
+/// Only the fields meaningful for recreating the node are kept, so it's cheap to store one per
+/// node, and it can be built by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeMetadata {
+    /// Size in bytes, for symlinks, it's the length of the target path.
+    pub len: u64,
+    /// Last modification time, `None` if the platform doesn't support it.
+    pub modified: Option<SystemTime>,
+    /// Unix permission bits (like `0o755`), `None` on other platforms.
+    pub mode: Option<u32>,
+}
+
+impl From<&Metadata> for NodeMetadata {
+    fn from(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+
+            Some(metadata.permissions().mode() & 0o7777)
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+
+        Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            mode,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Implementation of [`NodeKind`].
+
+use std::path::PathBuf;
+
+use file_type_enum::FileType;
+
+use crate::FsTree;
+
+/// The kind of a single [`FsTree`] node, without its children.
+///
+/// Useful for flat representations of a tree, where each entry is a path and a kind.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NodeKind {
+    /// A regular file.
+    Regular,
+    /// A directory.
+    Directory,
+    /// Symbolic link, and it's target path.
+    Symlink(PathBuf),
+}
+
+impl NodeKind {
+    /// The [`FileType`] of this kind.
+    pub fn file_type(&self) -> FileType {
+        match self {
+            Self::Regular => FileType::Regular,
+            Self::Directory => FileType::Directory,
+            Self::Symlink(_) => FileType::Symlink,
+        }
+    }
+}
+
+impl FsTree {
+    /// The [`NodeKind`] of this node, cloning the symlink target, if any.
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            Self::Regular => NodeKind::Regular,
+            Self::Directory(_) => NodeKind::Directory,
+            Self::Symlink(target) => NodeKind::Symlink(target.clone()),
+        }
+    }
+}
+
+impl From<NodeKind> for FsTree {
+    /// Directories are converted to empty directories.
+    fn from(kind: NodeKind) -> Self {
+        match kind {
+            NodeKind::Regular => Self::Regular,
+            NodeKind::Directory => Self::new_dir(),
+            NodeKind::Symlink(target) => Self::Symlink(target),
+        }
+    }
+}
@@ -0,0 +1,1613 @@
+//! Variations of [`FsTree::read_at`] that collect extra information while reading.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use crate::{
+    gitignore::{self, Gitignore},
+    utils::{self, fs},
+    Error, FsTree, NodeKind, NodeMetadata, Result, SideTable, TrieMap,
+};
+
+/// What to do with an entry visited by [`FsTree::__read_at_with`].
+pub(crate) enum Visit {
+    /// Read the entry children, if it's a directory.
+    Enter,
+    /// Don't read the entry children, directories are kept empty.
+    Prune,
+    /// Leave the entry out of the tree.
+    Skip,
+    /// Use the given node instead of reading the entry.
+    Replace(FsTree),
+}
+
+/// How symlink targets are stored, see [`FsTree::symlink_read_at_with_resolution`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TargetResolution {
+    /// The target as returned by `read_link`, the default of [`FsTree::symlink_read_at`].
+    #[default]
+    Raw,
+    /// The target with `.` and `..` components resolved lexically, without touching the
+    /// filesystem, so relative targets stay relative.
+    Lexical,
+    /// The absolute path the symlink (chain) points to, resolved by the filesystem.
+    Canonical,
+}
+
+/// Options for [`FsTree::read_at_with_options`].
+///
+/// The default options are the ones used by [`FsTree::read_at`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReadOptions {
+    max_symlink_hops: usize,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            max_symlink_hops: utils::MAX_SYMLINK_HOPS,
+        }
+    }
+}
+
+impl ReadOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of symlinks followed in a chain, defaults to 40, like Linux's
+    /// `SYMLOOP_MAX`.
+    ///
+    /// Longer chains are reported as [`Error::SymlinkLoopError`]. The OS has its own limit, so
+    /// values above it have no effect.
+    pub fn max_symlink_hops(mut self, arg: usize) -> Self {
+        self.max_symlink_hops = arg;
+        self
+    }
+}
+
+impl FsTree {
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, with the given `options`.
+    ///
+    /// With the default [`ReadOptions`], this is the same as [`FsTree::read_at`].
+    ///
+    /// # Errors:
+    ///
+    /// - [`Error::NotFoundError`] if `path` doesn't exist.
+    /// - [`Error::SymlinkLoopError`] if a symlink chain loops, or is longer than the limit.
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::{FsTree, ReadOptions};
+    ///
+    /// let options = ReadOptions::new().max_symlink_hops(8);
+    /// let tree = FsTree::read_at_with_options("path_here", options).unwrap();
+    /// ```
+    pub fn read_at_with_options(path: impl AsRef<Path>, options: ReadOptions) -> Result<Self> {
+        Self::__read_at(
+            &mut path.as_ref().to_path_buf(),
+            true,
+            options.max_symlink_hops,
+            &mut vec![],
+        )
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, also returning the
+    /// `(device, inode)` pair of each node.
+    ///
+    /// The returned map is keyed by the node path (relative to `path`), paths that share the same
+    /// pair point to the same file (hard links or bind mounts), and a change in the device means
+    /// a filesystem boundary was crossed.
+    ///
+    /// Only available on Unix.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    #[cfg(unix)]
+    #[allow(clippy::type_complexity)]
+    pub fn read_at_with_ids(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, BTreeMap<PathBuf, (u64, u64)>)> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut ids = BTreeMap::new();
+
+        let tree = Self::__read_at_with(path.as_ref(), true, &mut |_, relative, metadata| {
+            ids.insert(relative.to_path_buf(), (metadata.dev(), metadata.ino()));
+            Ok(Visit::Enter)
+        })?;
+
+        Ok((tree, ids))
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, also capturing the
+    /// [`NodeMetadata`] of each node.
+    ///
+    /// The metadata is keyed by the node path (relative to `path`), the root is the empty path.
+    ///
+    /// The metadata comes from the same `stat` calls done for reading, so there's no extra IO,
+    /// but [`FsTree::read_at`] is still cheaper if you don't need it.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    ///
+    /// use fs_tree::FsTree;
+    ///
+    /// let (tree, metadata) = FsTree::read_at_with_metadata("project").unwrap();
+    ///
+    /// let script = metadata[Path::new("build.sh")];
+    /// println!("mode: {:o}, modified: {:?}", script.mode.unwrap(), script.modified);
+    /// ```
+    pub fn read_at_with_metadata(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, SideTable<NodeMetadata>)> {
+        Self::__read_at_with_metadata(path.as_ref(), true)
+    }
+
+    /// Construct a `FsTree` by reading from `path`, symlink-aware, also capturing the
+    /// [`NodeMetadata`] of each node.
+    ///
+    /// Symlinks' own metadata is captured, not their targets'. See
+    /// [`FsTree::read_at_with_metadata`].
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    pub fn symlink_read_at_with_metadata(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, SideTable<NodeMetadata>)> {
+        Self::__read_at_with_metadata(path.as_ref(), false)
+    }
+
+    fn __read_at_with_metadata(
+        path: &Path,
+        follow_symlinks: bool,
+    ) -> Result<(Self, SideTable<NodeMetadata>)> {
+        let mut table = SideTable::new();
+
+        let tree = Self::__read_at_with(path, follow_symlinks, &mut |_, relative, metadata| {
+            table.insert(relative.to_path_buf(), NodeMetadata::from(metadata));
+            Ok(Visit::Enter)
+        })?;
+
+        Ok((tree, table))
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, but doesn't cross into
+    /// other filesystems.
+    ///
+    /// This is the `find -xdev` behavior, directories in a different device than `path`'s are
+    /// kept empty, and their paths are returned, so you know what was excluded.
+    ///
+    /// Only available on Unix.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    #[cfg(unix)]
+    pub fn read_at_same_fs(path: impl AsRef<Path>) -> Result<(Self, Vec<PathBuf>)> {
+        use std::os::unix::fs::MetadataExt;
+
+        Self::__read_at_same_fs(path.as_ref(), |_, metadata| metadata.dev())
+    }
+
+    /// `device` is a parameter so tests can simulate mount points.
+    #[cfg(unix)]
+    fn __read_at_same_fs(
+        path: &Path,
+        device: impl Fn(&Path, &std::fs::Metadata) -> u64,
+    ) -> Result<(Self, Vec<PathBuf>)> {
+        let mut root_device = None;
+        let mut skipped = vec![];
+
+        let tree = Self::__read_at_with(path, true, &mut |_, relative, metadata| {
+            let device = device(relative, metadata);
+            let root_device = *root_device.get_or_insert(device);
+
+            if metadata.is_dir() && device != root_device {
+                skipped.push(relative.to_path_buf());
+                Ok(Visit::Prune)
+            } else {
+                Ok(Visit::Enter)
+            }
+        })?;
+
+        Ok((tree, skipped))
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, but reads each real
+    /// directory only once.
+    ///
+    /// When following symlinks, multiple paths can lead to the same directory, the first one
+    /// visited is read normally, but subsequent encounters (compared by canonical path) are kept
+    /// as empty directories, and returned in a map from the duplicate path to the path where the
+    /// directory contents were read (both relative to `path`).
+    ///
+    /// This also protects against symlink loops, because a symlink to an ancestor directory is a
+    /// duplicate of it.
+    ///
+    /// Which of the paths is considered the first depends on the order returned by the OS.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    pub fn read_at_dedup_dirs(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, BTreeMap<PathBuf, PathBuf>)> {
+        let mut visited = HashMap::<PathBuf, PathBuf>::new();
+        let mut duplicates = BTreeMap::new();
+
+        let tree = Self::__read_at_with(path.as_ref(), true, &mut |full, relative, metadata| {
+            if !metadata.is_dir() {
+                return Ok(Visit::Enter);
+            }
+
+            let canonical =
+                fs::canonicalize(full).map_err(|error| utils::io_error_at(error, full))?;
+
+            if let Some(original) = visited.get(&canonical) {
+                duplicates.insert(relative.to_path_buf(), original.clone());
+                Ok(Visit::Prune)
+            } else {
+                visited.insert(canonical, relative.to_path_buf());
+                Ok(Visit::Enter)
+            }
+        })?;
+
+        Ok((tree, duplicates))
+    }
+
+    /// Construct a `FsTree` by reading from `path`, symlink-aware, storing symlink targets as
+    /// chosen by `resolution`.
+    ///
+    /// With [`TargetResolution::Lexical`], a target like `./a/../b` is stored as `b`, note that
+    /// this might change where the link points to, if `a` is a symlink itself.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    /// - With [`TargetResolution::Canonical`], if any symlink is broken.
+    pub fn symlink_read_at_with_resolution(
+        path: impl AsRef<Path>,
+        resolution: TargetResolution,
+    ) -> Result<Self> {
+        Self::__read_at_with(path.as_ref(), false, &mut |full, _, metadata| {
+            if !metadata.is_symlink() {
+                return Ok(Visit::Enter);
+            }
+
+            let read_link = || fs::read_link(full).map_err(|error| utils::io_error_at(error, full));
+
+            let target = match resolution {
+                TargetResolution::Raw => read_link()?,
+                TargetResolution::Lexical => utils::normalize_lexically(&read_link()?),
+                TargetResolution::Canonical => {
+                    fs::canonicalize(full).map_err(|error| utils::io_error_at(error, full))?
+                },
+            };
+
+            Ok(Visit::Replace(Self::Symlink(target)))
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, symlink-aware, collecting the errors of
+    /// symlinks that can't be read instead of failing.
+    ///
+    /// A symlink whose target can't be read becomes a [`FsTree::Symlink`] with an empty target,
+    /// and its path (relative to `path`) is returned with the error, so the scan completes over a
+    /// partially-broken tree and you can report the problems afterwards.
+    ///
+    /// # Errors:
+    ///
+    /// - If any other IO error occurs.
+    /// - If any file has an unexpected file type.
+    #[allow(clippy::type_complexity)]
+    pub fn read_at_collecting_errors(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, Vec<(PathBuf, io::Error)>)> {
+        Self::__read_at_collecting_errors(path.as_ref(), |path| fs::read_link(path))
+    }
+
+    /// `read_link` is a parameter so tests can make it fail, which a real symlink rarely does.
+    #[allow(clippy::type_complexity)]
+    fn __read_at_collecting_errors(
+        path: &Path,
+        read_link: impl Fn(&Path) -> io::Result<PathBuf>,
+    ) -> Result<(Self, Vec<(PathBuf, io::Error)>)> {
+        let mut errors = vec![];
+
+        let tree = Self::__read_at_with(path, false, &mut |full, relative, metadata| {
+            if !metadata.is_symlink() {
+                return Ok(Visit::Enter);
+            }
+
+            let target = read_link(full).unwrap_or_else(|error| {
+                errors.push((relative.to_path_buf(), error));
+                PathBuf::new()
+            });
+
+            Ok(Visit::Replace(Self::Symlink(target)))
+        })?;
+
+        Ok((tree, errors))
+    }
+
+    /// Construct a `FsTree` by reading from `path`, symlink-aware, rewriting absolute symlink
+    /// targets that lie inside of `path` to be relative to the symlink's location.
+    ///
+    /// This makes the tree portable, so it can be written somewhere else while keeping its inner
+    /// links, targets outside of `path` (or already relative) are kept as is.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// // If `/project/docs/link` points to `/project/src/lib.rs`, it's read as `../src/lib.rs`
+    /// let tree = FsTree::read_at_portable("/project").unwrap();
+    /// ```
+    pub fn read_at_portable(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let canonical_root =
+            fs::canonicalize(path).map_err(|error| utils::io_error_at(error, path))?;
+
+        Self::__read_at_with(path, false, &mut |full, relative, metadata| {
+            if !metadata.is_symlink() {
+                return Ok(Visit::Enter);
+            }
+
+            let target = fs::read_link(full).map_err(|error| utils::io_error_at(error, full))?;
+
+            // Targets might refer to the root by the given path, or by its canonical form
+            let inner_target = [path, &canonical_root]
+                .into_iter()
+                .filter(|root| target.is_absolute() && root.is_absolute())
+                .find_map(|root| target.strip_prefix(root).ok());
+
+            let target = match inner_target {
+                Some(inner_target) => {
+                    let link_dir = relative.parent().unwrap_or(Path::new(""));
+                    utils::relative_path(link_dir, inner_target)
+                },
+                None => target,
+            };
+
+            Ok(Visit::Replace(Self::Symlink(target)))
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, also returning the position
+    /// of each entry in its parent directory, as listed by the OS.
+    ///
+    /// Children in a `TrieMap` are sorted by name, so the original listing order is lost, the
+    /// returned map lets you reproduce it by sorting siblings by their position.
+    ///
+    /// The root isn't included in the map.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    pub fn read_at_with_order(path: impl AsRef<Path>) -> Result<(Self, BTreeMap<PathBuf, usize>)> {
+        let mut next_position = HashMap::<PathBuf, usize>::new();
+        let mut order = BTreeMap::new();
+
+        let tree = Self::__read_at_with(path.as_ref(), true, &mut |_, relative, _| {
+            if let Some(parent) = relative.parent() {
+                let position = next_position.entry(parent.to_path_buf()).or_default();
+                order.insert(relative.to_path_buf(), *position);
+                *position += 1;
+            }
+
+            Ok(Visit::Enter)
+        })?;
+
+        Ok((tree, order))
+    }
+
+    /// Construct both the followed and the symlink-aware trees (in this order) by reading from
+    /// `path` once.
+    ///
+    /// Same as calling [`FsTree::read_at`] and [`FsTree::symlink_read_at`], but the followed tree
+    /// is derived from the symlink-aware one, by resolving symlinks against it, so directories
+    /// aren't read twice.
+    ///
+    /// Symlink targets that can't be resolved inside of the tree (absolute targets, targets that
+    /// escape `path`, or that go through other symlinks) are read from the filesystem.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    /// - If a symlink is broken, or points to one of its parent directories (a loop).
+    pub fn read_both(path: impl AsRef<Path>) -> Result<(Self, Self)> {
+        let path = path.as_ref();
+
+        let symlink_aware = Self::symlink_read_at(path)?;
+        let followed = symlink_aware.__resolve_symlinks(
+            &symlink_aware,
+            path,
+            &mut PathBuf::new(),
+            &mut vec![],
+        )?;
+
+        Ok((followed, symlink_aware))
+    }
+
+    /// Returns a copy of `self` (at `relative` inside of `tree`) with symlinks followed.
+    ///
+    /// `expanding` holds the paths of the symlinks currently being expanded, to detect loops.
+    fn __resolve_symlinks(
+        &self,
+        tree: &Self,
+        root: &Path,
+        relative: &mut PathBuf,
+        expanding: &mut Vec<PathBuf>,
+    ) -> Result<Self> {
+        match self {
+            Self::Regular => Ok(Self::Regular),
+            Self::Directory(children) => {
+                let mut resolved_children = TrieMap::new();
+
+                for (name, child) in children {
+                    relative.push(name);
+                    let resolved = child.__resolve_symlinks(tree, root, relative, expanding);
+                    relative.pop();
+
+                    resolved_children.insert(name.clone(), resolved?);
+                }
+
+                Ok(Self::Directory(resolved_children))
+            },
+            Self::Symlink(_) => {
+                let Some((mut target_path, target)) = resolve_in_tree(tree, relative) else {
+                    // Fallback to the filesystem
+                    return Self::read_at(root.join(&relative));
+                };
+
+                if target.is_regular() {
+                    return Ok(Self::Regular);
+                }
+
+                let is_loop = std::iter::once(&*relative)
+                    .chain(expanding.iter())
+                    .any(|path| path.starts_with(&target_path));
+
+                if is_loop {
+                    return Err(Error::SymlinkLoopError(root.join(&relative)));
+                }
+
+                expanding.push(relative.clone());
+                let resolved = target.__resolve_symlinks(tree, root, &mut target_path, expanding);
+                expanding.pop();
+
+                resolved
+            },
+        }
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, calling `progress` with the
+    /// number of entries read so far (including the root) every `every` entries.
+    ///
+    /// A lightweight alternative to [`FsTree::read_at_streaming`] for showing a spinner or a
+    /// percentage, if `every` is `0`, `progress` is never called.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    pub fn read_at_with_progress(
+        path: impl AsRef<Path>,
+        every: usize,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Self> {
+        let mut count = 0;
+        let mut until_next = every;
+
+        Self::__read_at_with(path.as_ref(), true, &mut |_, _, _| {
+            count += 1;
+
+            if every != 0 {
+                until_next -= 1;
+
+                if until_next == 0 {
+                    until_next = every;
+                    progress(count);
+                }
+            }
+
+            Ok(Visit::Enter)
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, also sending each entry
+    /// through `tx` as soon as it's found.
+    ///
+    /// Useful for showing live progress in another thread.
+    ///
+    /// Entries are sent in traversal order: the root first (with an empty path), then each
+    /// directory before its children, and siblings in the order returned by the OS, which is
+    /// **not** sorted. Only [`NodeKind::Regular`] and [`NodeKind::Directory`] are sent, because
+    /// symlinks are followed.
+    ///
+    /// If the receiver is dropped, reading continues without sending.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    pub fn read_at_streaming(
+        path: impl AsRef<Path>,
+        tx: Sender<(PathBuf, NodeKind)>,
+    ) -> Result<Self> {
+        Self::__read_at_with(path.as_ref(), true, &mut |_, relative, metadata| {
+            let kind = if metadata.is_dir() {
+                Some(NodeKind::Directory)
+            } else if metadata.is_file() {
+                Some(NodeKind::Regular)
+            } else {
+                None // Unexpected file type, errors right after
+            };
+
+            if let Some(kind) = kind {
+                let _ = tx.send((relative.to_path_buf(), kind));
+            }
+
+            Ok(Visit::Enter)
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, mapping each entry with `f`.
+    ///
+    /// `f` is called with the entry path (relative to `path`) and its kind, if it returns:
+    /// - `None`, the entry is skipped, directories are not read.
+    /// - The same kind, the entry is kept as is.
+    /// - A different kind, the entry is reclassified, directories are not read, and other entries
+    ///   become empty directories if mapped to [`NodeKind::Directory`].
+    ///
+    /// The root isn't passed to `f`.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// // Skip hidden files, and don't read `target/` contents
+    /// let tree = FsTree::read_at_mapped(".", |path, kind| {
+    ///     if path.file_name()?.as_encoded_bytes().starts_with(b".") {
+    ///         None
+    ///     } else if path == "target" {
+    ///         Some(fs_tree::NodeKind::Regular)
+    ///     } else {
+    ///         Some(kind)
+    ///     }
+    /// });
+    /// ```
+    pub fn read_at_mapped(
+        path: impl AsRef<Path>,
+        mut f: impl FnMut(&Path, NodeKind) -> Option<NodeKind>,
+    ) -> Result<Self> {
+        Self::__read_at_with(path.as_ref(), true, &mut |_, relative, metadata| {
+            let kind = if metadata.is_dir() {
+                NodeKind::Directory
+            } else if metadata.is_file() {
+                NodeKind::Regular
+            } else {
+                return Ok(Visit::Enter); // Unexpected file type, errors right after
+            };
+
+            if relative.as_os_str().is_empty() {
+                return Ok(Visit::Enter);
+            }
+
+            let visit = match f(relative, kind.clone()) {
+                None => Visit::Skip,
+                Some(new_kind) if new_kind == kind => Visit::Enter,
+                Some(new_kind) => Visit::Replace(new_kind.into()),
+            };
+
+            Ok(visit)
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, only including regular files
+    /// with one of the given `extensions` (without the leading dot, like `"rs"`).
+    ///
+    /// Directories are always traversed and kept, even if nothing inside of them matches, call
+    /// [`FsTree::prune_empty_dirs`] afterwards to remove these.
+    ///
+    /// If `ignore_case` is `true`, extensions are compared ASCII case-insensitively.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// let mut tree = FsTree::read_at_with_extensions(".", &["rs", "toml"], false).unwrap();
+    /// tree.prune_empty_dirs();
+    /// ```
+    pub fn read_at_with_extensions(
+        path: impl AsRef<Path>,
+        extensions: &[&str],
+        ignore_case: bool,
+    ) -> Result<Self> {
+        let matches = |extension: &OsStr| {
+            extensions.iter().any(|expected| {
+                if ignore_case {
+                    extension.eq_ignore_ascii_case(expected)
+                } else {
+                    extension == *expected
+                }
+            })
+        };
+
+        Self::__read_at_with(path.as_ref(), true, &mut |_, relative, metadata| {
+            if relative.as_os_str().is_empty() || !metadata.is_file() {
+                return Ok(Visit::Enter);
+            }
+
+            if relative.extension().is_some_and(matches) {
+                Ok(Visit::Enter)
+            } else {
+                Ok(Visit::Skip)
+            }
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, only reading directories
+    /// up to `max_depth` levels deep.
+    ///
+    /// Directories at `max_depth` aren't read, so they're kept empty, this saves syscalls when
+    /// only the top levels of a huge tree are needed. A depth of `0` returns just the root.
+    ///
+    /// See [`NodesIter::max_depth`] for the equivalent filter over a tree already in memory.
+    ///
+    /// [`NodesIter::max_depth`]: crate::iter::NodesIter::max_depth
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// let tree = FsTree::read_at_with_depth("/", 2).unwrap();
+    /// ```
+    pub fn read_at_with_depth(path: impl AsRef<Path>, max_depth: usize) -> Result<Self> {
+        Self::__read_at_with_depth(path.as_ref(), max_depth, true)
+    }
+
+    /// Construct a `FsTree` by reading from `path`, only reading directories up to `max_depth`
+    /// levels deep.
+    ///
+    /// This is the symlink-aware version of [`FsTree::read_at_with_depth`].
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    pub fn symlink_read_at_with_depth(path: impl AsRef<Path>, max_depth: usize) -> Result<Self> {
+        Self::__read_at_with_depth(path.as_ref(), max_depth, false)
+    }
+
+    fn __read_at_with_depth(path: &Path, max_depth: usize, follow_symlinks: bool) -> Result<Self> {
+        Self::__read_at_with(path, follow_symlinks, &mut |_, relative, _| {
+            if relative.components().count() < max_depth {
+                Ok(Visit::Enter)
+            } else {
+                Ok(Visit::Prune)
+            }
+        })
+    }
+
+    /// Construct a `FsTree` by reading from `path`, follows symlinks, leaving out the entries
+    /// ignored by the `.gitignore` files found along the way.
+    ///
+    /// `.gitignore`s stack: each one applies to its directory and everything below it, with
+    /// patterns relative to where it's located, and deeper files take precedence (a `!pattern`
+    /// can re-include what a parent ignored). Ignored directories aren't read at all, so their
+    /// contents can't be re-included, like in `git`.
+    ///
+    /// Supports the common subset of the syntax: `#` comments, `!` negation, trailing `/` for
+    /// directories only, leading or middle `/` for anchored patterns, and the wildcards `*`, `?`,
+    /// `[..]` and `**`. The `.git` directory and global excludes aren't treated specially.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs, including reading a `.gitignore`.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// let tree = FsTree::read_at_gitignore(".").unwrap();
+    /// ```
+    pub fn read_at_gitignore(path: impl AsRef<Path>) -> Result<Self> {
+        let mut ignores = BTreeMap::new();
+
+        Self::__read_at_with(path.as_ref(), true, &mut |full, relative, metadata| {
+            let is_dir = metadata.is_dir();
+
+            if gitignore::is_ignored(&ignores, relative, is_dir) {
+                return Ok(Visit::Skip);
+            }
+
+            if is_dir {
+                if let Some(gitignore) = Gitignore::read_at(&full.join(".gitignore"))? {
+                    ignores.insert(relative.to_path_buf(), gitignore);
+                }
+            }
+
+            Ok(Visit::Enter)
+        })
+    }
+
+    /// Reads the children of the directory at `path` (relative to `self`) from `root.join(path)`,
+    /// follows symlinks.
+    ///
+    /// Only one level is read, child directories are kept empty, so they can be expanded later.
+    /// This enables lazy exploration of huge trees, like in a file browser.
+    ///
+    /// The previous children at `path` are replaced.
+    ///
+    /// # Errors:
+    ///
+    /// - [`Error::NotFoundError`] if there's nothing at `path` in `self`, or on disk.
+    /// - [`Error::NotADirectoryError`] if the node at `path` isn't a directory, in `self`, or on
+    ///   disk.
+    /// - If any IO error occurs.
+    /// - If any file has an unexpected file type.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use fs_tree::FsTree;
+    ///
+    /// let mut tree = FsTree::new_dir();
+    ///
+    /// tree.expand_dir_at("", "/").unwrap();
+    /// tree.expand_dir_at("home", "/").unwrap();
+    /// ```
+    pub fn expand_dir_at(&mut self, path: impl AsRef<Path>, root: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let full_path = root.as_ref().join(path);
+
+        let children = match self.get_mut(path) {
+            Some(Self::Directory(children)) => children,
+            Some(_) => return Err(Error::NotADirectoryError(full_path)),
+            None => return Err(Error::NotFoundError(full_path)),
+        };
+
+        let expanded = Self::__read_at_with(&full_path, true, &mut |_, relative, _| {
+            if relative.as_os_str().is_empty() {
+                Ok(Visit::Enter)
+            } else {
+                Ok(Visit::Prune)
+            }
+        })?;
+
+        match expanded {
+            Self::Directory(expanded_children) => {
+                *children = expanded_children;
+                Ok(())
+            },
+            _ => Err(Error::NotADirectoryError(full_path)),
+        }
+    }
+
+    /// Reads the tree at `path`, calling `visit` for each entry (including the root) with its
+    /// full path, relative path, and metadata, to decide what to do with it.
+    ///
+    /// If the root is skipped, an empty directory is returned.
+    pub(crate) fn __read_at_with<F>(
+        path: &Path,
+        follow_symlinks: bool,
+        visit: &mut F,
+    ) -> Result<Self>
+    where
+        F: FnMut(&Path, &Path, &std::fs::Metadata) -> Result<Visit>,
+    {
+        let tree = Self::__read_entry_with(
+            path,
+            &mut PathBuf::new(),
+            follow_symlinks,
+            &mut vec![],
+            visit,
+        )?;
+        Ok(tree.unwrap_or_else(Self::new_dir))
+    }
+
+    /// `ancestors` holds the ids of the directories being read, to detect loops when following
+    /// symlinks.
+    fn __read_entry_with<F>(
+        path: &Path,
+        relative: &mut PathBuf,
+        follow_symlinks: bool,
+        ancestors: &mut Vec<(u64, u64)>,
+        visit: &mut F,
+    ) -> Result<Option<Self>>
+    where
+        F: FnMut(&Path, &Path, &std::fs::Metadata) -> Result<Visit>,
+    {
+        let metadata = if follow_symlinks {
+            fs::metadata(path).map_err(|error| utils::follow_error_at(error, path))?
+        } else {
+            fs::symlink_metadata(path).map_err(|error| utils::io_error_at(error, path))?
+        };
+
+        let action = match visit(path, relative, &metadata)? {
+            Visit::Skip => return Ok(None),
+            Visit::Replace(node) => return Ok(Some(node)),
+            action => action,
+        };
+        let file_type = metadata.file_type();
+
+        let node = if file_type.is_file() {
+            Self::Regular
+        } else if file_type.is_symlink() {
+            Self::Symlink(utils::follow_symlink(path)?)
+        } else if file_type.is_dir() {
+            let mut children = TrieMap::new();
+
+            if let Visit::Prune = action {
+                return Ok(Some(Self::Directory(children)));
+            }
+
+            // A symlink to a parent directory would be read forever
+            let id = utils::dir_id(&metadata).filter(|_| follow_symlinks);
+
+            if let Some(id) = id {
+                if ancestors.contains(&id) {
+                    return Err(Error::SymlinkLoopError(path.to_path_buf()));
+                }
+                ancestors.push(id);
+            }
+
+            let entries = fs::read_dir(path).map_err(|error| utils::io_error_at(error, path))?;
+
+            for entry in entries {
+                let entry = entry.map_err(|error| utils::io_error_at(error, path))?;
+                let name = PathBuf::from(entry.file_name());
+
+                relative.push(&name);
+                let child = Self::__read_entry_with(
+                    &entry.path(),
+                    relative,
+                    follow_symlinks,
+                    ancestors,
+                    visit,
+                )?;
+                relative.pop();
+
+                if let Some(child) = child {
+                    children.insert(name, child);
+                }
+            }
+
+            if id.is_some() {
+                ancestors.pop();
+            }
+
+            Self::Directory(children)
+        } else {
+            return Err(Error::UnexpectedFileTypeError(
+                file_type.into(),
+                path.to_path_buf(),
+            ));
+        };
+
+        Ok(Some(node))
+    }
+}
+
+/// Follows the symlink (chain) at `path` inside of `tree`, returning the final path and node.
+///
+/// Returns `None` if the chain can't be resolved inside of the tree.
+fn resolve_in_tree<'a>(tree: &'a FsTree, path: &Path) -> Option<(PathBuf, &'a FsTree)> {
+    let mut path = path.to_path_buf();
+
+    for _ in 0..utils::MAX_SYMLINK_HOPS {
+        let node = tree.get(&path)?;
+
+        let FsTree::Symlink(target) = node else {
+            return Some((path, node));
+        };
+
+        if target.is_absolute() {
+            return None;
+        }
+
+        let parent = path.parent().unwrap_or(Path::new(""));
+        let resolved = utils::normalize_lexically(&parent.join(target));
+
+        if resolved
+            .components()
+            .any(|component| component == Component::ParentDir)
+        {
+            return None;
+        }
+
+        path = resolved
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{tree, utils::testdir};
+
+    #[test]
+    fn test_read_at_with_options() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            file
+            one -> file
+            two -> one
+            three -> two
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let expected = tree! { file one two three };
+        let read = |hops| {
+            FsTree::read_at_with_options(test_dir, ReadOptions::new().max_symlink_hops(hops))
+        };
+
+        assert_eq!(read(3).unwrap(), expected);
+        assert_eq!(
+            FsTree::read_at_with_options(test_dir, ReadOptions::new()).unwrap(),
+            FsTree::read_at(test_dir).unwrap()
+        );
+
+        let error = read(2).unwrap_err();
+        assert!(matches!(error, Error::SymlinkLoopError(path) if path == test_dir.join("three")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_at_with_ids_detects_hard_links() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+            }
+            other
+        };
+        tree.write_at(test_dir).unwrap();
+        fs::hard_link(test_dir.join("dir/file"), test_dir.join("hard_link")).unwrap();
+
+        let (result, ids) = FsTree::read_at_with_ids(test_dir).unwrap();
+
+        assert_eq!(result.paths().count(), ids.len());
+        assert_eq!(ids[Path::new("dir/file")], ids[Path::new("hard_link")]);
+        assert_ne!(ids[Path::new("dir/file")], ids[Path::new("other")]);
+        assert_eq!(ids[Path::new("")].0, ids[Path::new("other")].0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_at_with_metadata() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                script
+            }
+            link -> "dir/script"
+        };
+        tree.write_at(test_dir).unwrap();
+        fs::write(test_dir.join("dir/script"), "echo").unwrap();
+        let permissions = std::fs::Permissions::from_mode(0o751);
+        fs::set_permissions(test_dir.join("dir/script"), permissions).unwrap();
+
+        let (result, metadata) = FsTree::read_at_with_metadata(test_dir).unwrap();
+        assert_eq!(result.paths().count(), metadata.len());
+
+        let script = metadata[Path::new("dir/script")];
+        assert_eq!(script.mode, Some(0o751));
+        assert_eq!(script.len, 4);
+        assert!(script.modified.is_some());
+        assert_eq!(metadata[Path::new("link")], script);
+
+        let (result, metadata) = FsTree::symlink_read_at_with_metadata(test_dir).unwrap();
+        assert_eq!(result, tree);
+        assert_eq!(metadata[Path::new("link")].len, "dir/script".len() as u64);
+    }
+
+    #[test]
+    fn test_read_at_streaming() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file1
+                file2
+            }
+            file3
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let result = FsTree::read_at_streaming(test_dir, tx).unwrap();
+        let entries: Vec<_> = rx.iter().collect();
+
+        assert_eq!(result, tree);
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0], ("".into(), NodeKind::Directory));
+
+        // Each entry is sent after its parent
+        for (index, (path, _)) in entries.iter().enumerate().skip(1) {
+            let parent = path.parent().unwrap();
+            assert!(entries[..index].iter().any(|(path, _)| path == parent));
+        }
+
+        let mut entries = entries;
+        entries.sort();
+        assert_eq!(entries, tree.into_sorted_pairs());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_at_dedup_dirs() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            real: {
+                file
+            }
+            link1 -> real
+            link2 -> real
+            "loop" -> "."
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let (result, duplicates) = FsTree::read_at_dedup_dirs(test_dir).unwrap();
+
+        // The root is the original of `loop`, and one of the 3 others is the original of the rest
+        assert_eq!(duplicates.len(), 3);
+        assert_eq!(duplicates[Path::new("loop")], Path::new(""));
+        assert_eq!(result["loop"], FsTree::new_dir());
+
+        let original = ["real", "link1", "link2"]
+            .map(Path::new)
+            .into_iter()
+            .find(|path| !duplicates.contains_key(*path))
+            .unwrap();
+        assert_eq!(result[original], tree! { file });
+
+        for (duplicate, duplicate_of) in duplicates.iter().filter(|(path, _)| *path != "loop") {
+            assert_eq!(duplicate_of, original);
+            assert_eq!(result[duplicate], FsTree::new_dir());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_both() {
+        let (_dropper, test_dir) = testdir().unwrap();
+        let (_outside_dropper, outside_dir) = testdir().unwrap();
+
+        tree! { outside_file }.write_at(outside_dir).unwrap();
+
+        let mut tree = tree! {
+            dir: {
+                file
+                inner: {
+                    deep
+                }
+                to_sibling -> "../other"
+            }
+            other: {
+                up -> "../dir/inner"
+            }
+            to_dir -> dir
+            to_file -> "./dir/file"
+            chain -> to_dir
+        };
+        tree.insert("outside", FsTree::Symlink(outside_dir.to_path_buf()));
+        tree.write_at(test_dir).unwrap();
+
+        let (followed, symlink_aware) = FsTree::read_both(test_dir).unwrap();
+
+        assert_eq!(followed, FsTree::read_at(test_dir).unwrap());
+        assert_eq!(symlink_aware, tree);
+        assert_eq!(followed["outside"], tree! { outside_file });
+        assert_eq!(followed["chain"], followed["dir"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_both_errors() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! { dir: { up -> ".." } }.write_at(test_dir).unwrap();
+        let error = FsTree::read_both(test_dir).unwrap_err();
+        assert!(matches!(error, Error::SymlinkLoopError(path) if path == test_dir.join("dir/up")));
+
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! { broken -> missing }.write_at(test_dir).unwrap();
+        assert!(FsTree::read_both(test_dir).is_err());
+    }
+
+    #[test]
+    fn test_read_at_with_progress() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file1
+                file2
+            }
+            file3
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let mut calls = vec![];
+        let result = FsTree::read_at_with_progress(test_dir, 2, |count| calls.push(count)).unwrap();
+
+        assert_eq!(result, tree);
+        assert_eq!(calls, [2, 4]);
+
+        let mut calls = vec![];
+        FsTree::read_at_with_progress(test_dir, 0, |count| calls.push(count)).unwrap();
+        assert!(calls.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_read_at_with_resolution() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            dir: {
+                file
+                link -> "./../dir/./file"
+            }
+            up -> "../outside"
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let read = |resolution| FsTree::symlink_read_at_with_resolution(test_dir, resolution);
+
+        let result = read(TargetResolution::Raw).unwrap();
+        assert_eq!(result, FsTree::symlink_read_at(test_dir).unwrap());
+        assert_eq!(
+            result["dir/link"],
+            FsTree::Symlink("./../dir/./file".into())
+        );
+
+        let result = read(TargetResolution::Lexical).unwrap();
+        assert_eq!(result["dir/link"], FsTree::Symlink("../dir/file".into()));
+        assert_eq!(result["up"], FsTree::Symlink("../outside".into()));
+
+        // `up` is broken
+        assert!(read(TargetResolution::Canonical).is_err());
+        fs::remove_file(test_dir.join("up")).unwrap();
+
+        let result = read(TargetResolution::Canonical).unwrap();
+        let canonical_file = fs::canonicalize(test_dir.join("dir/file")).unwrap();
+        assert_eq!(result["dir/link"], FsTree::Symlink(canonical_file));
+    }
+
+    #[test]
+    fn test_read_at_collecting_errors() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                file
+                link -> file
+            }
+            broken -> missing
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let (result, errors) = FsTree::read_at_collecting_errors(test_dir).unwrap();
+
+        assert_eq!(result, tree);
+        assert!(errors.is_empty());
+
+        // Fail to read one of the symlinks, as if it was removed in the middle of the read
+        let (result, errors) = FsTree::__read_at_collecting_errors(test_dir, |path| {
+            if path.ends_with("dir/link") {
+                Err(io::Error::from(io::ErrorKind::NotFound))
+            } else {
+                fs::read_link(path)
+            }
+        })
+        .unwrap();
+
+        let expected = tree! {
+            dir: {
+                file
+                link -> ""
+            }
+            broken -> missing
+        };
+        assert_eq!(result, expected);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, Path::new("dir/link"));
+        assert_eq!(errors[0].1.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_at_portable() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let mut tree = tree! {
+            dir: {
+                file
+            }
+            nested: {
+                deep: {}
+            }
+            relative -> "dir/file"
+        };
+        tree.insert("dir/sibling", FsTree::Symlink(test_dir.join("dir/file")));
+        tree.insert("nested/deep/up", FsTree::Symlink(test_dir.join("dir")));
+        tree.insert("root", FsTree::Symlink(test_dir.to_path_buf()));
+        tree.insert("outside", FsTree::Symlink("/usr/bin".into()));
+        tree.write_at(test_dir).unwrap();
+
+        let result = FsTree::read_at_portable(test_dir).unwrap();
+
+        let expected = tree! {
+            dir: {
+                file
+                sibling -> file
+            }
+            nested: {
+                deep: {
+                    up -> "../../dir"
+                }
+            }
+            relative -> "dir/file"
+            root -> "."
+            outside -> "/usr/bin"
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_read_at_with_order() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                a
+                b
+                c
+            }
+            d
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let (result, order) = FsTree::read_at_with_order(test_dir).unwrap();
+        assert_eq!(result, tree);
+
+        let mut positions = ["dir/a", "dir/b", "dir/c"].map(|path| order[Path::new(path)]);
+        positions.sort();
+        assert_eq!(positions, [0, 1, 2]);
+
+        let mut positions = ["dir", "d"].map(|path| order[Path::new(path)]);
+        positions.sort();
+        assert_eq!(positions, [0, 1]);
+
+        assert_eq!(order.len(), 5);
+    }
+
+    #[test]
+    fn test_read_at_with_extensions() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            "main.rs"
+            "Cargo.TOML"
+            "notes.txt"
+            src: {
+                "lib.rs"
+                "data.json"
+            }
+            docs: {
+                "index.md"
+            }
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let result = FsTree::read_at_with_extensions(test_dir, &["rs", "toml"], false).unwrap();
+        let expected = tree! {
+            "main.rs"
+            src: {
+                "lib.rs"
+            }
+            docs: {}
+        };
+        assert_eq!(result, expected);
+
+        let mut result = FsTree::read_at_with_extensions(test_dir, &["rs", "toml"], true).unwrap();
+        result.prune_empty_dirs();
+        let expected = tree! {
+            "main.rs"
+            "Cargo.TOML"
+            src: {
+                "lib.rs"
+            }
+        };
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_read_at_with_depth() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            a: {
+                b: {
+                    c: {
+                        d
+                    }
+                }
+                link -> b
+            }
+            e
+        };
+        tree.write_at(test_dir).unwrap();
+
+        assert_eq!(
+            FsTree::read_at_with_depth(test_dir, 0).unwrap(),
+            FsTree::new_dir()
+        );
+        assert_eq!(
+            FsTree::read_at_with_depth(test_dir, 1).unwrap(),
+            tree! { a: {} e }
+        );
+        assert_eq!(
+            FsTree::read_at_with_depth(test_dir, 2).unwrap(),
+            tree! { a: { b: {} link: {} } e }
+        );
+        assert_eq!(
+            FsTree::symlink_read_at_with_depth(test_dir, 2).unwrap(),
+            tree! { a: { b: {} link -> b } e }
+        );
+        assert_eq!(
+            FsTree::symlink_read_at_with_depth(test_dir, usize::MAX).unwrap(),
+            tree
+        );
+    }
+
+    #[test]
+    fn test_read_at_gitignore() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            ".gitignore"
+            "debug.log"
+            target: {
+                binary
+            }
+            src: {
+                ".gitignore"
+                "lib.rs"
+                "generated.rs"
+                "important.log"
+            }
+        };
+        let contents = BTreeMap::from([
+            (PathBuf::from(".gitignore"), "*.log\ntarget/\n"),
+            (
+                PathBuf::from("src/.gitignore"),
+                "generated.rs\n!important.log\n",
+            ),
+        ]);
+
+        tree.write_at_with(test_dir, |path| {
+            contents.get(path).map(|text| text.as_bytes().to_vec())
+        })
+        .unwrap();
+
+        let result = FsTree::read_at_gitignore(test_dir).unwrap();
+        let expected = tree! {
+            ".gitignore"
+            src: {
+                ".gitignore"
+                "lib.rs"
+                "important.log"
+            }
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_expand_dir_at() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            a: {
+                b: {
+                    c
+                }
+            }
+            d
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let mut tree = FsTree::new_dir();
+
+        tree.expand_dir_at("", test_dir).unwrap();
+        assert_eq!(tree, tree! { a: {} d });
+
+        tree.expand_dir_at("a", test_dir).unwrap();
+        assert_eq!(tree, tree! { a: { b: {} } d });
+
+        tree.expand_dir_at("a/b", test_dir).unwrap();
+        assert_eq!(tree, tree! { a: { b: { c } } d });
+
+        let error = tree.expand_dir_at("d", test_dir).unwrap_err();
+        assert!(matches!(error, Error::NotADirectoryError(path) if path == test_dir.join("d")));
+
+        let error = tree.expand_dir_at("missing", test_dir).unwrap_err();
+        assert!(matches!(error, Error::NotFoundError(path) if path == test_dir.join("missing")));
+    }
+
+    #[test]
+    fn test_read_at_mapped() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            skipped_dir: {
+                file
+            }
+            reclassified_dir: {
+                file
+            }
+            reclassified_file
+            kept: {
+                skipped_file
+                file
+            }
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let result = FsTree::read_at_mapped(test_dir, |path, kind| {
+            match path.file_name()?.to_str()? {
+                "skipped_dir" | "skipped_file" => None,
+                "reclassified_dir" => Some(NodeKind::Regular),
+                "reclassified_file" => Some(NodeKind::Directory),
+                _ => Some(kind),
+            }
+        })
+        .unwrap();
+
+        let expected = tree! {
+            reclassified_dir
+            reclassified_file: {}
+            kept: {
+                file
+            }
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_at_same_fs_without_mount_points() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        let tree = tree! {
+            dir: {
+                inner: {
+                    file
+                }
+            }
+            file
+        };
+        tree.write_at(test_dir).unwrap();
+
+        let (result, skipped) = FsTree::read_at_same_fs(test_dir).unwrap();
+
+        assert_eq!(result, tree);
+        assert!(skipped.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_at_same_fs_skips_mount_points() {
+        use std::os::unix::fs::MetadataExt;
+
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            dir: {
+                mnt: {
+                    file
+                    nested: {}
+                }
+                file
+            }
+            link_to_mnt -> "dir/mnt"
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        // Simulate another filesystem mounted at `dir/mnt`
+        let device = |relative: &Path, metadata: &std::fs::Metadata| {
+            let is_mounted = relative.starts_with("dir/mnt") || relative.starts_with("link_to_mnt");
+            metadata.dev() + u64::from(is_mounted)
+        };
+        let (result, mut skipped) = FsTree::__read_at_same_fs(test_dir, device).unwrap();
+        // Depends on the order returned by the OS
+        skipped.sort();
+
+        let expected = tree! {
+            dir: {
+                mnt: {}
+                file
+            }
+            link_to_mnt: {}
+        };
+        assert_eq!(result, expected);
+        assert_eq!(skipped, [Path::new("dir/mnt"), Path::new("link_to_mnt")]);
+    }
+}
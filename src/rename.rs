@@ -0,0 +1,281 @@
+//! Moving nodes within a [`FsTree`].
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::{FsTree, InsertError, RenameError};
+
+/// Options for [`FsTree::rename_with`].
+///
+/// The default options are the ones used by [`FsTree::rename`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenameOptions {
+    create_parents: bool,
+    overwrite: bool,
+}
+
+impl RenameOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the missing parent directories of the destination, like `mkdir -p`.
+    pub fn create_parents(mut self, arg: bool) -> Self {
+        self.create_parents = arg;
+        self
+    }
+
+    /// Replace the node at the destination, if any, instead of failing.
+    pub fn overwrite(mut self, arg: bool) -> Self {
+        self.overwrite = arg;
+        self
+    }
+}
+
+impl FsTree {
+    /// Moves the node at `from` to `to`, both relative to the root.
+    ///
+    /// The parent of `to` must already be a directory, and `to` must be free, see
+    /// [`FsTree::rename_with`] to change that. Renaming a node to its own path does nothing.
+    ///
+    /// The tree is left unchanged if an error is returned.
+    ///
+    /// # Errors:
+    ///
+    /// - [`RenameError::NotFound`] if there's no node at `from`.
+    /// - [`RenameError::IntoDescendant`] if `to` is inside of `from`.
+    /// - [`RenameError::AlreadyExists`] if there's a node at `to`.
+    /// - [`RenameError::Destination`] if a parent of `to` is missing, or isn't a directory.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::tree;
+    ///
+    /// let mut tree = tree! {
+    ///     src: {
+    ///         "main.rs"
+    ///     }
+    ///     bin: {}
+    /// };
+    ///
+    /// tree.rename("src/main.rs", "bin/app.rs").unwrap();
+    ///
+    /// assert_eq!(tree, tree! { src: {} bin: { "app.rs" } });
+    /// ```
+    pub fn rename(
+        &mut self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+    ) -> Result<(), RenameError> {
+        self.rename_with(from, to, RenameOptions::default())
+    }
+
+    /// Moves the node at `from` to `to`, with the given [`RenameOptions`].
+    ///
+    /// See [`FsTree::rename`].
+    ///
+    /// # Errors:
+    ///
+    /// - [`RenameError::NotFound`] if there's no node at `from`.
+    /// - [`RenameError::IntoDescendant`] if `to` is inside of `from`.
+    /// - [`RenameError::AlreadyExists`] if there's a node at `to`, unless overwriting, the root
+    ///   can't be overwritten.
+    /// - [`RenameError::Destination`] if a parent of `to` isn't a directory, or is missing,
+    ///   unless creating parents.
+    pub fn rename_with(
+        &mut self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+        options: RenameOptions,
+    ) -> Result<(), RenameError> {
+        let from = without_cur_dirs(from.as_ref());
+        let to = without_cur_dirs(to.as_ref());
+
+        if !self.contains(&from) {
+            return Err(RenameError::NotFound(from));
+        }
+        if from == to {
+            return Ok(());
+        }
+        if to.starts_with(&from) {
+            return Err(RenameError::IntoDescendant { from, to });
+        }
+
+        // The root always exists, and can't be overwritten, because it holds `from`
+        let Some(name) = to.file_name() else {
+            return Err(RenameError::AlreadyExists(to));
+        };
+
+        let parent = to.parent().unwrap_or(Path::new(""));
+        self.check_destination_parent(parent, options.create_parents)
+            .map_err(RenameError::Destination)?;
+
+        if !options.overwrite && self.contains(&to) {
+            return Err(RenameError::AlreadyExists(to));
+        }
+
+        // Checked above, `from` isn't the root, and the destination parent is reachable
+        let node = self.remove(&from).expect("node exists");
+        self.get_or_insert_dir(parent).insert(name, node);
+
+        Ok(())
+    }
+
+    /// Checks that `parent` is a directory, or that it can be created if `create_parents`.
+    fn check_destination_parent(
+        &self,
+        parent: &Path,
+        create_parents: bool,
+    ) -> Result<(), InsertError> {
+        let mut node = self;
+        let mut path = PathBuf::new();
+
+        for piece in parent.iter() {
+            let Self::Directory(children) = node else {
+                break;
+            };
+
+            path.push(piece);
+
+            node = match children.get(Path::new(piece)) {
+                Some(child) => child,
+                None if create_parents => return Ok(()),
+                None => return Err(InsertError::MissingParent(path)),
+            };
+        }
+
+        if node.is_dir() {
+            Ok(())
+        } else {
+            Err(InsertError::NotADirectory {
+                path,
+                variant: node.variant_str(),
+            })
+        }
+    }
+}
+
+fn without_cur_dirs(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tree;
+
+    fn sample() -> FsTree {
+        tree! {
+            a: {
+                b: {
+                    file
+                }
+            }
+            c: {}
+            link -> target
+        }
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut tree = sample();
+
+        tree.rename("a/b", "c/moved").unwrap();
+        let expected = tree! {
+            a: {}
+            c: {
+                moved: {
+                    file
+                }
+            }
+            link -> target
+        };
+        assert_eq!(tree, expected);
+
+        // `.`s are ignored, and renaming to the same path does nothing
+        tree.rename("./link", "link").unwrap();
+        assert_eq!(tree, expected);
+
+        // Moving out to the root
+        tree.rename("c/moved/file", "file").unwrap();
+        assert_eq!(tree["file"], FsTree::Regular);
+        assert!(!tree.contains("c/moved/file"));
+    }
+
+    #[test]
+    fn test_rename_missing_source() {
+        let mut tree = sample();
+
+        let result = tree.rename("missing", "c/missing");
+        assert_eq!(result, Err(RenameError::NotFound("missing".into())));
+        assert_eq!(tree, sample());
+    }
+
+    #[test]
+    fn test_rename_into_descendant() {
+        let mut tree = sample();
+
+        let result = tree.rename("a", "a/b/a");
+        let expected = RenameError::IntoDescendant {
+            from: "a".into(),
+            to: "a/b/a".into(),
+        };
+        assert_eq!(result, Err(expected));
+
+        // The root contains everything
+        assert!(matches!(
+            tree.rename("", "new"),
+            Err(RenameError::IntoDescendant { .. })
+        ));
+        assert_eq!(tree, sample());
+    }
+
+    #[test]
+    fn test_rename_occupied_destination() {
+        let mut tree = sample();
+
+        let result = tree.rename("link", "a/b/file");
+        assert_eq!(result, Err(RenameError::AlreadyExists("a/b/file".into())));
+        assert_eq!(tree, sample());
+
+        let options = RenameOptions::new().overwrite(true);
+        tree.rename_with("link", "a/b/file", options).unwrap();
+        assert_eq!(tree["a/b/file"], FsTree::Symlink("target".into()));
+        assert!(!tree.contains("link"));
+
+        let result = tree.rename_with("c", "", options);
+        assert_eq!(result, Err(RenameError::AlreadyExists("".into())));
+    }
+
+    #[test]
+    fn test_rename_destination_parents() {
+        let mut tree = sample();
+
+        let result = tree.rename("link", "x/y/link");
+        let expected = RenameError::Destination(InsertError::MissingParent("x".into()));
+        assert_eq!(result, Err(expected));
+
+        let result = tree.rename("link", "a/b/file/link");
+        let expected = RenameError::Destination(InsertError::NotADirectory {
+            path: "a/b/file".into(),
+            variant: "regular file",
+        });
+        assert_eq!(result, Err(expected.clone()));
+        assert_eq!(tree, sample());
+
+        // Creating parents still can't go through files
+        let options = RenameOptions::new().create_parents(true);
+        let result = tree.rename_with("link", "a/b/file/x/link", options);
+        assert_eq!(result, Err(expected));
+        assert_eq!(tree, sample());
+
+        tree.rename_with("link", "x/y/link", options).unwrap();
+        assert_eq!(tree["x/y/link"], FsTree::Symlink("target".into()));
+        assert!(!tree.contains("link"));
+    }
+}
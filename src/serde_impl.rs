@@ -0,0 +1,207 @@
+//! [`Serialize`] and [`Deserialize`] for [`FsTree`], enabled by the `serde` feature.
+//!
+//! The format is:
+//! - Directories are maps from file names to their children.
+//! - Regular files are unit (`null` in JSON).
+//! - Symlinks are maps with a single `"symlink"` key, pointing to the target.
+//!
+//! ```json
+//! { "dir": { "file": null, "link": { "symlink": "../target" } } }
+//! ```
+//!
+//! A directory holding a single regular file named `symlink` (`{ "symlink": null }`) isn't
+//! ambiguous, because targets are always strings.
+//!
+//! Deserializing requires a self-describing format (like JSON), and paths must be valid UTF-8 in
+//! order to be serialized.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{
+    de::{self, MapAccess, Visitor},
+    ser::{self, SerializeMap},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{FsTree, TrieMap};
+
+const SYMLINK_KEY: &str = "symlink";
+
+impl Serialize for FsTree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Regular => serializer.serialize_unit(),
+            Self::Directory(children) => {
+                let mut map = serializer.serialize_map(Some(children.len()))?;
+                for (name, child) in children {
+                    map.serialize_entry(utf8::<S::Error>(name)?, child)?;
+                }
+                map.end()
+            },
+            Self::Symlink(target) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry(SYMLINK_KEY, utf8::<S::Error>(target)?)?;
+                map.end()
+            },
+        }
+    }
+}
+
+fn utf8<E: ser::Error>(path: &Path) -> Result<&str, E> {
+    path.to_str()
+        .ok_or_else(|| E::custom(format!("path {} is not valid UTF-8", path.display())))
+}
+
+impl<'de> Deserialize<'de> for FsTree {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match deserializer.deserialize_any(EntryVisitor)? {
+            Entry::Node(node) => Ok(node),
+            Entry::Target(_) => Err(de::Error::custom("expected a node, found a symlink target")),
+        }
+    }
+}
+
+/// A value inside of a map, it's a target if under the `"symlink"` key, or a node otherwise.
+enum Entry {
+    Node(FsTree),
+    Target(PathBuf),
+}
+
+impl<'de> Deserialize<'de> for Entry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(EntryVisitor)
+    }
+}
+
+struct EntryVisitor;
+
+impl<'de> Visitor<'de> for EntryVisitor {
+    type Value = Entry;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a regular file (unit), a directory (map) or a symlink target (string)")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Entry, E> {
+        Ok(Entry::Node(FsTree::Regular))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Entry, E> {
+        Ok(Entry::Node(FsTree::Regular))
+    }
+
+    fn visit_str<E: de::Error>(self, target: &str) -> Result<Entry, E> {
+        Ok(Entry::Target(target.into()))
+    }
+
+    fn visit_string<E: de::Error>(self, target: String) -> Result<Entry, E> {
+        Ok(Entry::Target(target.into()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entry, A::Error> {
+        let mut children = TrieMap::new();
+        let mut target = None;
+
+        while let Some((name, entry)) = map.next_entry::<String, Entry>()? {
+            match entry {
+                Entry::Node(node) => {
+                    children.insert(name.into(), node);
+                },
+                Entry::Target(path) if name == SYMLINK_KEY => target = Some(path),
+                Entry::Target(_) => {
+                    return Err(de::Error::custom(format!(
+                        "unexpected symlink target at {name:?}, targets must be under \
+                         {SYMLINK_KEY:?}"
+                    )));
+                },
+            }
+        }
+
+        match target {
+            None => Ok(Entry::Node(FsTree::Directory(children))),
+            Some(target) if children.is_empty() => Ok(Entry::Node(FsTree::Symlink(target))),
+            Some(_) => {
+                Err(de::Error::custom(format!(
+                    "symlink map must only contain the {SYMLINK_KEY:?} key"
+                )))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use crate::{tree, FsTree};
+
+    #[test]
+    fn test_serde_json_round_trip() {
+        let tree = tree! {
+            config1
+            config2
+            outer_dir: {
+                file1
+                file2
+                inner_dir: {
+                    inner1
+                    inner2
+                    inner3
+                    inner_link -> inner_target
+                }
+            }
+            link -> target
+            config3
+        };
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let result: FsTree = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(result, tree);
+    }
+
+    #[test]
+    fn test_serde_json_format() {
+        let tree = tree! {
+            dir: {
+                file
+                link -> "../target"
+            }
+            symlink
+            empty: {}
+        };
+
+        let expected = json!({
+            "dir": {
+                "file": null,
+                "link": { "symlink": "../target" },
+            },
+            "symlink": null,
+            "empty": {},
+        });
+
+        assert_eq!(serde_json::to_value(&tree).unwrap(), expected);
+        assert_eq!(serde_json::from_value::<FsTree>(expected).unwrap(), tree);
+        assert_eq!(
+            serde_json::from_value::<FsTree>(json!(null)).unwrap(),
+            FsTree::Regular
+        );
+    }
+
+    #[test]
+    fn test_serde_json_rejects_misplaced_targets() {
+        let cases = [
+            json!("target"),
+            json!({ "file": "target" }),
+            json!({ "symlink": "target", "other": null }),
+        ];
+
+        for case in cases {
+            assert!(serde_json::from_value::<FsTree>(case).is_err());
+        }
+    }
+}
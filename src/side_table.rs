@@ -0,0 +1,144 @@
+//! Data attached to the nodes of a [`FsTree`], see [`SideTable`].
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::FsTree;
+
+/// Data attached to the nodes of a [`FsTree`], keyed by their paths relative to the root.
+///
+/// The keys are the same paths yielded by [`FsTree::iter`], so the root is the empty path. Nodes
+/// might have no entry, and the table isn't updated when the tree changes, see
+/// [`FsTree::retain_side_table`] to drop stale entries.
+///
+/// This keeps [`FsTree`] free of a generic parameter, so trees with different kinds of data (or
+/// none) are still the same type.
+///
+/// # Examples:
+///
+/// ```
+/// use std::path::Path;
+///
+/// use fs_tree::{tree, SideTable};
+///
+/// let tree = tree! {
+///     src: {
+///         lib
+///     }
+///     link -> src
+/// };
+///
+/// // Tag every node with its depth
+/// let depths: SideTable<usize> = tree.side_table(|path, _| Some(path.components().count()));
+///
+/// assert_eq!(depths[Path::new("")], 0);
+/// assert_eq!(depths[Path::new("src/lib")], 2);
+/// ```
+pub type SideTable<T> = BTreeMap<PathBuf, T>;
+
+impl FsTree {
+    /// Builds a [`SideTable`] by calling `f` for every node, in the [`FsTree::iter`] order.
+    ///
+    /// `f` receives the path of each node, relative to the root, and the node itself, nodes for
+    /// which it returns `None` have no entry.
+    pub fn side_table<T>(&self, mut f: impl FnMut(&Path, &Self) -> Option<T>) -> SideTable<T> {
+        self.iter()
+            .filter_map(|(node, path)| {
+                let data = f(&path, node)?;
+                Some((path, data))
+            })
+            .collect()
+    }
+
+    /// Iterates over `(node, path, data)` for every node, pairing it with its entry in `table`.
+    ///
+    /// Entries of `table` for paths that aren't in the tree are ignored.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, SideTable};
+    ///
+    /// let tree = tree! {
+    ///     file
+    ///     other
+    /// };
+    ///
+    /// let tags = SideTable::from([("file".into(), "important")]);
+    ///
+    /// let tagged: Vec<_> = tree
+    ///     .iter_with_side_table(&tags)
+    ///     .filter_map(|(_, path, tag)| Some((path, *tag?)))
+    ///     .collect();
+    ///
+    /// assert_eq!(tagged, [("file".into(), "important")]);
+    /// ```
+    pub fn iter_with_side_table<'a, T>(
+        &'a self,
+        table: &'a SideTable<T>,
+    ) -> impl Iterator<Item = (&'a Self, PathBuf, Option<&'a T>)> + 'a {
+        self.iter().map(|(node, path)| {
+            let data = table.get(&path);
+            (node, path, data)
+        })
+    }
+
+    /// Removes the entries of `table` whose paths aren't in the tree.
+    ///
+    /// Useful after removing nodes from the tree, to keep both in sync.
+    pub fn retain_side_table<T>(&self, table: &mut SideTable<T>) {
+        table.retain(|path, _| self.contains(path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tree;
+
+    #[test]
+    fn test_side_table() {
+        let mut tree = tree! {
+            dir: {
+                file1
+                link -> target
+            }
+            file2
+        };
+
+        let targets = tree.side_table(|_, node| node.target().map(Path::to_path_buf));
+        assert_eq!(
+            targets,
+            SideTable::from([("dir/link".into(), "target".into())])
+        );
+
+        let kinds = tree.side_table(|_, node| Some(node.variant_str()));
+        let paired: Vec<_> = tree
+            .iter_with_side_table(&kinds)
+            .map(|(node, path, kind)| (path, *kind.unwrap() == node.variant_str()))
+            .collect();
+        assert_eq!(
+            paired,
+            [
+                ("".into(), true),
+                ("dir".into(), true),
+                ("dir/file1".into(), true),
+                ("dir/link".into(), true),
+                ("file2".into(), true),
+            ]
+        );
+
+        let mut kinds = kinds;
+        kinds.insert("missing".into(), "regular file");
+        tree.remove("dir");
+        tree.retain_side_table(&mut kinds);
+
+        let expected =
+            SideTable::from([("".into(), "directory"), ("file2".into(), "regular file")]);
+        assert_eq!(kinds, expected);
+    }
+}
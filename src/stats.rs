@@ -0,0 +1,243 @@
+//! Aggregate statistics of a filesystem tree.
+
+use std::{io, path::Path};
+
+use crate::{utils::fs, FsTree};
+
+/// Counts of each node type in a tree, and other aggregated values, see [`FsTree::scan_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    /// Number of regular files.
+    pub regular_files: usize,
+    /// Number of directories, including the root, if it's a directory.
+    pub directories: usize,
+    /// Number of symlinks.
+    pub symlinks: usize,
+    /// Sum of the sizes of all regular files, in bytes.
+    pub total_bytes: u64,
+    /// Depth of the deepest node, the root is at depth `0`.
+    pub max_depth: usize,
+}
+
+/// Counts of each node type in a [`FsTree`], see [`FsTree::stats`] and [`FsTree::stats_at`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FsTreeStats {
+    /// Number of regular files.
+    pub regular_files: usize,
+    /// Number of directories, including the root, if it's a directory.
+    pub directories: usize,
+    /// Number of symlinks, broken or not.
+    pub symlinks: usize,
+    /// Number of symlinks whose targets don't exist.
+    ///
+    /// Checking it requires the filesystem, so it's `None` for [`FsTree::stats`], and `Some` for
+    /// [`FsTree::stats_at`].
+    pub broken_symlinks: Option<usize>,
+    /// Depth of the deepest node, the root is at depth `0`.
+    pub max_depth: usize,
+}
+
+impl FsTree {
+    /// Compute the [`FsTreeStats`] of this tree, in a single traversal, without doing any IO.
+    ///
+    /// Since broken symlinks can't be told apart without IO, `broken_symlinks` is `None`, see
+    /// [`FsTree::stats_at`].
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use fs_tree::{tree, FsTreeStats};
+    ///
+    /// let tree = tree! {
+    ///     dir: {
+    ///         file
+    ///     }
+    ///     link -> dir
+    /// };
+    ///
+    /// let expected = FsTreeStats {
+    ///     regular_files: 1,
+    ///     directories: 2,
+    ///     symlinks: 1,
+    ///     broken_symlinks: None,
+    ///     max_depth: 2,
+    /// };
+    ///
+    /// assert_eq!(tree.stats(), expected);
+    /// ```
+    pub fn stats(&self) -> FsTreeStats {
+        let mut stats = FsTreeStats::default();
+        let mut nodes = self.nodes();
+
+        while let Some(node) = nodes.next() {
+            stats.max_depth = stats.max_depth.max(nodes.depth());
+
+            match node {
+                Self::Regular => stats.regular_files += 1,
+                Self::Directory(_) => stats.directories += 1,
+                Self::Symlink(_) => stats.symlinks += 1,
+            }
+        }
+
+        stats
+    }
+
+    /// Compute the [`FsTreeStats`] of this tree, counting broken symlinks as if `self` was at
+    /// `base`.
+    ///
+    /// Same as [`FsTree::stats`], with `broken_symlinks` from [`FsTree::count_broken_symlinks`].
+    pub fn stats_at(&self, base: impl AsRef<Path>) -> FsTreeStats {
+        FsTreeStats {
+            broken_symlinks: Some(self.count_broken_symlinks(base)),
+            ..self.stats()
+        }
+    }
+
+    /// Counts the symlinks whose targets don't exist, as if `self` was at `base`.
+    ///
+    /// Relative targets are resolved against the parent of each link, and targets are followed,
+    /// so the filesystem is checked, but the links themselves don't need to exist on disk.
+    pub fn count_broken_symlinks(&self, base: impl AsRef<Path>) -> usize {
+        let base = base.as_ref();
+
+        self.iter()
+            .filter_map(|(node, path)| Some((node.target()?, path)))
+            .filter(|(target, path)| {
+                let link_dir = base.join(path.parent().unwrap_or(Path::new("")));
+                fs::metadata(link_dir.join(target)).is_err()
+            })
+            .count()
+    }
+
+    /// Compute the [`TreeStats`] of the tree at `path`, without building it.
+    ///
+    /// This is a memory-efficient alternative to reading the tree just to aggregate it, useful for
+    /// very large trees.
+    ///
+    /// Symlinks are not followed, and other file types (like FIFOs and sockets) are not counted.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs.
+    pub fn scan_stats(path: impl AsRef<Path>) -> io::Result<TreeStats> {
+        let mut stats = TreeStats::default();
+        scan_stats_at(path.as_ref(), 0, &mut stats)?;
+        Ok(stats)
+    }
+}
+
+fn scan_stats_at(path: &Path, depth: usize, stats: &mut TreeStats) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    let file_type = metadata.file_type();
+
+    stats.max_depth = stats.max_depth.max(depth);
+
+    if file_type.is_file() {
+        stats.regular_files += 1;
+        stats.total_bytes += metadata.len();
+    } else if file_type.is_symlink() {
+        stats.symlinks += 1;
+    } else if file_type.is_dir() {
+        stats.directories += 1;
+
+        for entry in fs::read_dir(path)? {
+            scan_stats_at(&entry?.path(), depth + 1, stats)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{tree, utils::testdir};
+
+    #[test]
+    fn test_scan_stats() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            dir: {
+                inner: {
+                    file
+                }
+                link -> target
+            }
+            empty: {}
+            sized
+        }
+        .write_at(test_dir)
+        .unwrap();
+        fs::write(test_dir.join("sized"), "12345").unwrap();
+        fs::write(test_dir.join("dir/inner/file"), "123").unwrap();
+
+        let expected = TreeStats {
+            regular_files: 2,
+            directories: 4,
+            symlinks: 1,
+            total_bytes: 8,
+            max_depth: 3,
+        };
+
+        assert_eq!(FsTree::scan_stats(test_dir).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_stats() {
+        let tree = tree! {
+            file1: {}
+            outer_dir: {
+                file2
+                inner_dir: {
+                    file3
+                    link1 -> target1
+                }
+            }
+            link2 -> target2
+            file4
+        };
+
+        let expected = FsTreeStats {
+            regular_files: 3,
+            directories: 4,
+            symlinks: 2,
+            broken_symlinks: None,
+            max_depth: 3,
+        };
+
+        assert_eq!(tree.stats(), expected);
+        assert_eq!(FsTree::Regular.stats().regular_files, 1);
+    }
+
+    #[test]
+    fn test_count_broken_symlinks() {
+        let (_dropper, test_dir) = testdir().unwrap();
+
+        tree! {
+            dir: {
+                file
+            }
+        }
+        .write_at(test_dir)
+        .unwrap();
+
+        let tree = tree! {
+            dir: {
+                ok -> file
+                broken -> missing
+            }
+            ok -> "dir/file"
+            broken -> "dir/missing"
+        };
+
+        assert_eq!(tree.count_broken_symlinks(test_dir), 2);
+        assert_eq!(tree.count_broken_symlinks(test_dir.join("dir")), 4);
+
+        let stats = tree.stats_at(test_dir);
+        assert_eq!(stats.symlinks, 4);
+        assert_eq!(stats.broken_symlinks, Some(2));
+    }
+}
@@ -0,0 +1,185 @@
+//! Reading a [`FsTree`] from a tar archive, enabled by the `tar` feature.
+
+use std::{io::Read, path::Component};
+
+use file_type_enum::FileType;
+use tar::EntryType;
+
+use crate::{Error, FsTree, NodeKind, Result};
+
+impl FsTree {
+    /// Construct a `FsTree` from the entry headers of a tar archive.
+    ///
+    /// Regular files (including hard links) become [`FsTree::Regular`], directories become
+    /// [`FsTree::Directory`], and symlinks become [`FsTree::Symlink`] with their targets.
+    /// Contents are skipped, and parent directories missing from the archive are created, see
+    /// [`FsTree::from_entries`] for how duplicated entries are handled.
+    ///
+    /// Only available with the `tar` feature.
+    ///
+    /// # Errors:
+    ///
+    /// - If any IO error occurs while reading the archive, or if it's malformed.
+    /// - [`Error::UnexpectedFileTypeError`] for device files and FIFOs.
+    /// - [`Error::EscapingPathError`] if an entry path has a `..` component, or is absolute, so
+    ///   trees read from untrusted archives can't point outside of where they're written.
+    ///
+    /// # Examples:
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use fs_tree::FsTree;
+    ///
+    /// let archive = File::open("archive.tar").unwrap();
+    /// let tree = FsTree::from_tar(archive).unwrap();
+    ///
+    /// println!("{tree}");
+    /// ```
+    pub fn from_tar(reader: impl Read) -> Result<Self> {
+        let mut archive = tar::Archive::new(reader);
+        let mut entries = vec![];
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path = entry.path()?.into_owned();
+
+            let is_escaping = path.components().any(|component| {
+                matches!(
+                    component,
+                    Component::ParentDir | Component::RootDir | Component::Prefix(_)
+                )
+            });
+            if is_escaping {
+                return Err(Error::EscapingPathError(path));
+            }
+
+            let kind = match entry.header().entry_type() {
+                EntryType::Regular
+                | EntryType::Continuous
+                | EntryType::GNUSparse
+                | EntryType::Link => NodeKind::Regular,
+                EntryType::Directory => NodeKind::Directory,
+                EntryType::Symlink => {
+                    let target = entry.link_name()?.unwrap_or_default();
+                    NodeKind::Symlink(target.into_owned())
+                },
+                EntryType::Char => {
+                    return Err(Error::UnexpectedFileTypeError(FileType::CharDevice, path))
+                },
+                EntryType::Block => {
+                    return Err(Error::UnexpectedFileTypeError(FileType::BlockDevice, path))
+                },
+                EntryType::Fifo => {
+                    return Err(Error::UnexpectedFileTypeError(FileType::Fifo, path))
+                },
+                // Metadata entries, like global PAX headers, aren't files
+                _ => continue,
+            };
+
+            entries.push((path, kind));
+        }
+
+        Ok(Self::from_entries(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, path::Path};
+
+    use pretty_assertions::assert_eq;
+    use tar::{Builder, EntryType, Header};
+
+    use crate::{tree, Error, FsTree};
+
+    fn header(entry_type: EntryType, size: u64) -> Header {
+        let mut header = Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(0o644);
+        header
+    }
+
+    #[test]
+    fn test_from_tar() {
+        let mut builder = Builder::new(vec![]);
+
+        builder
+            .append_data(&mut header(EntryType::Directory, 0), "dir/", io::empty())
+            .unwrap();
+        builder
+            .append_data(
+                &mut header(EntryType::Regular, 5),
+                "dir/file",
+                &b"hello"[..],
+            )
+            .unwrap();
+        builder
+            .append_data(
+                &mut header(EntryType::Regular, 0),
+                "missing_parent/file",
+                io::empty(),
+            )
+            .unwrap();
+        builder
+            .append_link(&mut header(EntryType::Symlink, 0), "dir/link", "file")
+            .unwrap();
+        builder
+            .append_data(&mut header(EntryType::Directory, 0), "empty/", io::empty())
+            .unwrap();
+
+        let archive = builder.into_inner().unwrap();
+        let result = FsTree::from_tar(archive.as_slice()).unwrap();
+
+        let expected = tree! {
+            dir: {
+                file
+                link -> file
+            }
+            empty: {}
+            missing_parent: {
+                file
+            }
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_from_tar_rejects_escaping_paths() {
+        for path in ["../escaped", "dir/../../escaped", "/etc/passwd"] {
+            // `Header::set_path` refuses these, so the name is written directly
+            let mut header = header(EntryType::Regular, 0);
+            let name = &mut header.as_old_mut().name;
+            name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_cksum();
+
+            let mut builder = Builder::new(vec![]);
+            builder.append(&header, io::empty()).unwrap();
+
+            let archive = builder.into_inner().unwrap();
+            let error = FsTree::from_tar(archive.as_slice()).unwrap_err();
+
+            assert!(
+                matches!(error, Error::EscapingPathError(escaping) if escaping == Path::new(path))
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_tar_rejects_devices() {
+        let mut builder = Builder::new(vec![]);
+
+        builder
+            .append_data(&mut header(EntryType::Fifo, 0), "fifo", io::empty())
+            .unwrap();
+
+        let archive = builder.into_inner().unwrap();
+        let error = FsTree::from_tar(archive.as_slice()).unwrap_err();
+
+        assert!(
+            matches!(error, Error::UnexpectedFileTypeError(_, path) if path == Path::new("fifo"))
+        );
+    }
+}
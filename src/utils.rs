@@ -1,6 +1,10 @@
 #[cfg(not(feature = "fs-err"))]
 pub(crate) use std::fs;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashSet,
+    io,
+    path::{Component, Path, PathBuf},
+};
 
 use file_type_enum::FileType;
 #[cfg(feature = "fs-err")]
@@ -21,7 +25,191 @@ pub(crate) fn follow_symlink<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
         return Err(Error::NotASymlinkError(path.to_path_buf()));
     }
 
-    let target = fs::read_link(path)?;
+    let target = fs::read_link(path).map_err(|error| io_error_at(error, path))?;
 
     Ok(target)
 }
+
+/// Maximum number of symlinks followed in a chain, same limit as Linux's `SYMLOOP_MAX`.
+pub(crate) const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Checks if following the symlink chain at `path` loops, or takes more than `max_hops` hops.
+///
+/// Broken chains and IO errors don't count as loops.
+pub(crate) fn is_symlink_loop(path: &Path, max_hops: usize) -> bool {
+    let mut visited = HashSet::new();
+    let mut path = path.to_path_buf();
+
+    for _ in 0..=max_hops {
+        let is_symlink = fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.is_symlink());
+        let Some(target) = is_symlink.then(|| fs::read_link(&path).ok()).flatten() else {
+            return false;
+        };
+
+        path = match path.parent() {
+            Some(parent) => normalize_lexically(&parent.join(target)),
+            None => target,
+        };
+
+        if !visited.insert(path.clone()) {
+            return true;
+        }
+    }
+
+    true
+}
+
+/// Converts an IO error that happened while following `path` into an [`Error`], like
+/// [`io_error_at`], but reporting [`Error::SymlinkLoopError`] if it's a symlink loop.
+pub(crate) fn follow_error_at(error: io::Error, path: &Path) -> Error {
+    if is_symlink_loop(path, MAX_SYMLINK_HOPS) {
+        Error::SymlinkLoopError(path.to_path_buf())
+    } else {
+        io_error_at(error, path)
+    }
+}
+
+/// Identifies a directory by its `(device, inode)` pair, to detect when it's entered again
+/// through a symlink, only available on Unix.
+#[cfg(unix)]
+pub(crate) fn dir_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Identifies a directory by its `(device, inode)` pair, to detect when it's entered again
+/// through a symlink, only available on Unix.
+#[cfg(not(unix))]
+pub(crate) fn dir_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Creates a symlink at `link` pointing to `target`.
+///
+/// On Windows, file and directory symlinks are different, `target_is_dir` is only called there to
+/// pick between them.
+#[cfg(unix)]
+pub(crate) fn create_symlink(
+    target: &Path,
+    link: &Path,
+    _target_is_dir: impl FnOnce() -> bool,
+) -> io::Result<()> {
+    #[cfg(not(feature = "fs-err"))]
+    use std::os::unix::fs::symlink;
+
+    #[cfg(feature = "fs-err")]
+    use fs_err::os::unix::fs::symlink;
+
+    symlink(target, link)
+}
+
+/// Creates a symlink at `link` pointing to `target`.
+///
+/// On Windows, file and directory symlinks are different, `target_is_dir` is only called there to
+/// pick between them.
+#[cfg(windows)]
+pub(crate) fn create_symlink(
+    target: &Path,
+    link: &Path,
+    target_is_dir: impl FnOnce() -> bool,
+) -> io::Result<()> {
+    #[cfg(not(feature = "fs-err"))]
+    use std::os::windows::fs::{symlink_dir, symlink_file};
+
+    #[cfg(feature = "fs-err")]
+    use fs_err::os::windows::fs::{symlink_dir, symlink_file};
+
+    if target_is_dir() {
+        symlink_dir(target, link)
+    } else {
+        symlink_file(target, link)
+    }
+}
+
+/// Removes the symlink at `link`, without touching its target.
+///
+/// On Windows, directory symlinks must be removed as directories.
+pub(crate) fn remove_symlink(link: &Path) -> io::Result<()> {
+    match fs::remove_file(link) {
+        Err(_) if cfg!(windows) => fs::remove_dir(link),
+        result => result,
+    }
+}
+
+/// Converts an IO error that happened at `path` into an [`Error`] that carries the path, using
+/// the specific variants for [`io::ErrorKind::NotFound`] and [`io::ErrorKind::NotADirectory`].
+pub(crate) fn io_error_at(error: io::Error, path: &Path) -> Error {
+    match error.kind() {
+        io::ErrorKind::NotFound => Error::NotFoundError(path.to_path_buf()),
+        io::ErrorKind::NotADirectory => Error::NotADirectoryError(path.to_path_buf()),
+        _ => Error::PathIoError(error, path.to_path_buf()),
+    }
+}
+
+/// Returns the path that leads from the directory `from` to `to`, both relative to the same base.
+///
+/// Returns `"."` if both are the same.
+pub(crate) fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let shared = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let ups = from.components().skip(shared).map(|_| Component::ParentDir);
+    let path: PathBuf = ups.chain(to.components().skip(shared)).collect();
+
+    if path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        path
+    }
+}
+
+/// Normalizes `path` without touching the filesystem, removing `.` components and resolving
+/// `..` against the preceding component.
+///
+/// Leading `..` components of relative paths are kept, and `..` right after the root is removed.
+pub(crate) fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut components: Vec<Component> = vec![];
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                match components.last() {
+                    Some(Component::Normal(_)) => {
+                        components.pop();
+                    },
+                    Some(Component::RootDir | Component::Prefix(_)) => {},
+                    _ => components.push(component),
+                }
+            },
+            component => components.push(component),
+        }
+    }
+
+    if components.is_empty() {
+        PathBuf::from(".")
+    } else {
+        components.iter().collect()
+    }
+}
+
+/// Returns `true` if the last piece of `path` is `.`, which [`Path::components`] would hide.
+pub(crate) fn ends_with_cur_dir(path: &Path) -> bool {
+    match path.as_os_str().as_encoded_bytes() {
+        [b'.'] => true,
+        [.., separator, b'.'] => std::path::is_separator((*separator).into()),
+        _ => false,
+    }
+}
+
+/// Creates a temporary directory for tests, the path is leaked for convenience.
+#[cfg(test)]
+pub(crate) fn testdir() -> std::io::Result<(tempfile::TempDir, &'static Path)> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().to_path_buf().into_boxed_path();
+    Ok((dir, Box::leak(path)))
+}